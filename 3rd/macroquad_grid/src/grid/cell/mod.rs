@@ -7,5 +7,8 @@
 pub struct Cell {
     pub color: Option<macroquad::color::Color>,
     pub text: String,
+    pub caption: String,
+    // fraction of the cell (from the left) to fill, and with what color
+    pub fill: Option<(f32, macroquad::color::Color)>,
     pub text_color: Option<macroquad::color::Color>,
 }
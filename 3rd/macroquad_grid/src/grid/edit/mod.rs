@@ -0,0 +1,89 @@
+use macroquad::input::{get_char_pressed, is_key_pressed, KeyCode};
+
+/// what happened to the text being edited this frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditEvent {
+    /// enter was pressed, heres the text
+    Commit(String),
+    /// escape was pressed, the text is gone
+    Cancel,
+}
+
+// the text being typed into a cell and where the caret is
+// caret is a char index (not a byte index) so it can't land mid char
+#[derive(Debug, Clone, Default)]
+pub struct Editor {
+    pub row: usize,
+    pub col: usize,
+    pub text: String,
+    pub caret: usize,
+}
+
+impl Editor {
+    pub fn new(row: usize, col: usize, text: String) -> Self {
+        let caret = text.chars().count();
+        Editor {
+            row,
+            col,
+            text,
+            caret,
+        }
+    }
+
+    fn byte_index(&self, caret: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(caret)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    /// reads this frames keyboard input
+    ///
+    /// typed chars go in at the caret, backspace/delete remove around it,
+    /// left/right/home/end move it
+    pub fn update(&mut self) -> Option<EditEvent> {
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() {
+                let index = self.byte_index(self.caret);
+                self.text.insert(index, c);
+                self.caret += 1;
+            }
+        }
+        let len = self.text.chars().count();
+        if is_key_pressed(KeyCode::Backspace) && self.caret > 0 {
+            self.caret -= 1;
+            let index = self.byte_index(self.caret);
+            self.text.remove(index);
+        }
+        if is_key_pressed(KeyCode::Delete) && self.caret < len {
+            let index = self.byte_index(self.caret);
+            self.text.remove(index);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            self.caret = self.caret.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.caret = (self.caret + 1).min(self.text.chars().count());
+        }
+        if is_key_pressed(KeyCode::Home) {
+            self.caret = 0;
+        }
+        if is_key_pressed(KeyCode::End) {
+            self.caret = self.text.chars().count();
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return Some(EditEvent::Commit(self.text.clone()));
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(EditEvent::Cancel);
+        }
+        None
+    }
+
+    /// the text with a caret drawn in
+    pub fn display(&self) -> String {
+        let mut text = self.text.clone();
+        text.insert(self.byte_index(self.caret), '|');
+        text
+    }
+}
@@ -1,8 +1,13 @@
 use macroquad::prelude::*;
 
 mod cell;
+mod edit;
 mod position;
 
+// how much of a cell's height goes to its caption (if it has one)
+const CAPTION_RATIO: f32 = 0.3;
+
+pub use edit::EditEvent;
 pub use position::Position;
 
 /// # the point of this crate!
@@ -29,6 +34,7 @@ pub use position::Position;
 /// - writing text to a specific cell
 /// - writing text to the selected cell
 /// - getting the selected cell's index
+/// - typing text into a cell
 /// - drawing the grid
 pub struct Grid {
     pub width: f32,               // width of the grid in pixels
@@ -49,6 +55,9 @@ pub struct Grid {
 
     selected_cell: Option<(usize, usize)>, // selected cell (if needed)
     selected_color: Option<macroquad::color::Color>,
+
+    editor: Option<edit::Editor>, // the cell being typed into (if any)
+    pub edit_color: macroquad::color::Color,
 }
 
 impl Default for Grid {
@@ -65,6 +74,8 @@ impl Default for Grid {
             gap_color: PINK,
             selected_cell: None,
             selected_color: Some(BLUE),
+            editor: None,
+            edit_color: YELLOW,
             // ignore the HORRID line below this comment
             // it just makes a 2D list of cell::default's
             // there are HEIGHT inner lists and they all have WIDTH elements
@@ -172,6 +183,8 @@ impl Grid {
             gap_color: BLACK,
             selected_cell: None,
             selected_color: Some(BLUE),
+            editor: None,
+            edit_color: YELLOW,
             // ignore the HORRID line below this comment
             // it just makes a 2D list of cell::default's
             // there are HEIGHT inner lists and they all have WIDTH elements
@@ -243,56 +256,92 @@ impl Grid {
         let x_pos = x_offset + self.gap + col as f32 * (cell_width + self.gap as f32);
         let y_pos = y_offset + self.gap + row as f32 * (cell_height + self.gap as f32);
 
+        // the cell being edited shows what is typed instead of its text
+        let editor = self
+            .editor
+            .as_ref()
+            .filter(|editor| (editor.row, editor.col) == (row, col));
+
         // cell color
         let mut color = self.cell_bg_color;
+        // being edited beats everything
+        if editor.is_some() {
+            color = self.edit_color;
+        }
         // if this is the selected_cell, use the other color
-        if let Some((selected_row, selected_col)) = self.selected_cell {
-            if selected_row == row && selected_col == col {
-                color = self
-                    .selected_color
-                    .expect("there was a selected cell but no selected color");
-            }
+        else if self.selected_cell == Some((row, col)) {
+            color = self
+                .selected_color
+                .expect("there was a selected cell but no selected color");
         }
         // and if it had a preset color then use that
         else if let Some(set_color) = self.cells[row][col].color {
-            // somehow we never reach this??
             color = set_color;
         }
 
         // draw it!
         draw_rectangle(x_pos, y_pos, cell_width, cell_height, color);
 
+        // partially fill it, like a progress bar
+        if let Some((fraction, fill_color)) = self.cells[row][col].fill.filter(|_| editor.is_none())
+        {
+            let fill_width = cell_width * fraction.clamp(0.0, 1.0);
+            draw_rectangle(x_pos, y_pos, fill_width, cell_height, fill_color);
+        }
+
         // draw the text if this cell has any
-        let text = &self.cells[row][col].text;
-        if !text.is_empty() {
-            // shifted because read the readme
-            let y_pos = y_pos + cell_height;
-
-            // center the text or something idk
-            let mut cell_height = cell_height;
-            let mut text = text.as_str();
-            loop {
-                let text_dim = macroquad::text::measure_text(text, None, cell_height as u16, 1.0); // 1.0 is default
-                if self.auto_resize_text && text_dim.width > cell_width {
-                    cell_height *= cell_width / text_dim.width * 0.9;
-                    continue;
-                } else if text_dim.width > cell_width {
-                    let char_count = text.chars().count();
-                    let mut it = text.chars();
-                    for _ in 0..(char_count as f32 * cell_width / text_dim.width) as usize {
-                        it.next();
-                    }
-                    text = &text[..text.len() - it.as_str().len()];
-                    continue;
-                }
-                let centered_x = (cell_width - text_dim.width) / 2.0 + x_pos;
-                let centered_y = y_pos - (cell_height - text_dim.height) / 2.0;
+        // a caption takes the top part of the cell and the text gets the rest
+        let cell = &self.cells[row][col];
+        let edited_text = editor.map(|editor| editor.display());
+        let text = edited_text.as_deref().unwrap_or(&cell.text);
+        if cell.caption.is_empty() {
+            self.draw_text_in(text, x_pos, y_pos, cell_width, cell_height);
+        } else {
+            let caption_height = cell_height * CAPTION_RATIO;
+            self.draw_text_in(&cell.caption, x_pos, y_pos, cell_width, caption_height);
+            self.draw_text_in(
+                text,
+                x_pos,
+                y_pos + caption_height,
+                cell_width,
+                cell_height - caption_height,
+            );
+        }
+        // draw_rectangle_lines(x_pos, y_pos, cell_width, cell_height, 1.0, BLACK);
+    }
 
-                draw_text(text, centered_x, centered_y, cell_height, BLACK);
-                break;
+    // draws one line of text centered in the given box
+    // shrinking (or cutting off) the text if it does not fit
+    fn draw_text_in(&self, text: &str, x_pos: f32, y_pos: f32, box_width: f32, box_height: f32) {
+        if text.is_empty() {
+            return;
+        }
+        // shifted because read the readme
+        let y_pos = y_pos + box_height;
+
+        // center the text or something idk
+        let mut font_size = box_height;
+        let mut text = text;
+        loop {
+            let text_dim = macroquad::text::measure_text(text, None, font_size as u16, 1.0); // 1.0 is default
+            if self.auto_resize_text && text_dim.width > box_width {
+                font_size *= box_width / text_dim.width * 0.9;
+                continue;
+            } else if text_dim.width > box_width {
+                let char_count = text.chars().count();
+                let mut it = text.chars();
+                for _ in 0..(char_count as f32 * box_width / text_dim.width) as usize {
+                    it.next();
+                }
+                text = &text[..text.len() - it.as_str().len()];
+                continue;
             }
+            let centered_x = (box_width - text_dim.width) / 2.0 + x_pos;
+            let centered_y = y_pos - (box_height - text_dim.height) / 2.0;
+
+            draw_text(text, centered_x, centered_y, font_size, BLACK);
+            break;
         }
-        // draw_rectangle_lines(x_pos, y_pos, cell_width, cell_height, 1.0, BLACK);
     }
 
     pub fn select_from_mouse(&mut self) -> Option<(usize, usize)> {
@@ -352,6 +401,39 @@ impl Grid {
         self.cells[row][col].color = Some(color);
     }
 
+    /// # fill part of a cell
+    ///
+    /// draws a bar over the cell's bg color (but under its text) covering
+    /// `fraction` (0.0 to 1.0) of the cell's width, starting from the left.
+    /// pass None to remove the bar
+    ///
+    /// ## panics
+    /// if the row or col is out of bounds
+    pub fn fill_cell(
+        &mut self,
+        row: usize,
+        col: usize,
+        fill: Option<(f32, macroquad::color::Color)>,
+    ) {
+        self.cells[row][col].fill = fill;
+    }
+
+    /// undoes color_cell, the cell goes back to the default bg color
+    ///
+    /// ## panics
+    /// if the row or col is out of bounds
+    pub fn clear_cell_color(&mut self, row: usize, col: usize) {
+        self.cells[row][col].color = None;
+    }
+
+    /// undoes color_cell (and fill_cell) for every cell
+    pub fn clear_colors(&mut self) {
+        for cell in self.cells.iter_mut().flatten() {
+            cell.color = None;
+            cell.fill = None;
+        }
+    }
+
     /// # sets default bg color for all cells
     ///
     /// different from color_cell becuase this one applies to all
@@ -395,6 +477,60 @@ impl Grid {
         &mut self.cells[row][col].text
     }
 
+    /// # write a caption to a cell
+    ///
+    /// the caption is a smaller line of text drawn above the cell's text,
+    /// handy for labelling what the text is
+    ///
+    /// ## panics
+    /// if row and col are out of bounds
+    pub fn set_cell_caption<T>(&mut self, row: usize, col: usize, caption: Option<T>)
+    where
+        T: ToString,
+    {
+        self.cells[row][col].caption = caption.map(|val| val.to_string()).unwrap_or_default();
+    }
+
+    /// # start typing into a cell
+    ///
+    /// the cell shows `text` with a caret (in edit_color) until
+    /// update_editing says enter or escape was pressed, or stop_editing is called.
+    /// only one cell can be edited at a time, this replaces any other edit
+    pub fn start_editing(&mut self, row: usize, col: usize, text: impl Into<String>) {
+        self.editor = Some(edit::Editor::new(row, col, text.into()));
+    }
+
+    /// drops the edit without telling anyone
+    pub fn stop_editing(&mut self) {
+        self.editor = None;
+    }
+
+    /// returns the (row, col) index of the cell being edited
+    pub fn editing_cell(&self) -> Option<(usize, usize)> {
+        self.editor.as_ref().map(|editor| (editor.row, editor.col))
+    }
+
+    /// keeps the typed text but shows it in another cell,
+    /// for when the cells got shuffled around
+    pub fn move_editor(&mut self, row: usize, col: usize) {
+        if let Some(editor) = &mut self.editor {
+            (editor.row, editor.col) = (row, col);
+        }
+    }
+
+    /// # feed this frames keyboard input to the edited cell
+    ///
+    /// call it once per frame while editing, it eats the chars typed
+    /// so dont also call get_char_pressed for them.
+    /// the edit is over once this returns Some
+    pub fn update_editing(&mut self) -> Option<EditEvent> {
+        let event = self.editor.as_mut()?.update();
+        if event.is_some() {
+            self.editor = None;
+        }
+        event
+    }
+
     /// same as set_cell_text
     /// but instead of providing a row and col
     /// it just writes the text onto the selected cell
@@ -59,6 +59,7 @@
 //!
 mod grid;
 
+pub use grid::EditEvent;
 pub use grid::Grid;
 pub use grid::Position;
 
@@ -0,0 +1,244 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+
+use crate::countdown::Countdown;
+use crate::deadline::Deadline;
+use crate::stopwatch::Stopwatch;
+use crate::{duration, Arg, Labeled};
+
+/// `$XDG_RUNTIME_DIR/osd-timer.sock`.
+pub fn default_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty())?;
+    Some(PathBuf::from(dir).join("osd-timer.sock"))
+}
+
+/// One line sent to the control socket. Commands that act on a stopwatch
+/// take its name or number last, and default to the active one:
+///
+/// - `status`
+/// - `start [timer]`, `pause [timer]`, `toggle [timer]`, `reset [timer]`
+/// - `adjust +5m [timer]` or `adjust -5m [timer]`
+/// - `task text`, for the active stopwatch
+/// - `add-stopwatch name`
+/// - `add-countdown label=duration`, taking what `--countdown` takes
+/// - `add-deadline label=deadline`, taking what `--deadline` takes
+/// - `quit`
+///
+/// The `add-` commands merge into what is there: a timer that already exists
+/// is left alone, and a deadline replaces the one with the same label.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Status,
+    Start(Option<String>),
+    Pause(Option<String>),
+    Toggle(Option<String>),
+    Reset(Option<String>),
+    Increment(Option<String>, Duration),
+    Decrement(Option<String>, Duration),
+    SetTask(String),
+    AddStopwatch(String),
+    AddCountdown(Labeled<Duration>),
+    AddDeadline(Labeled<Deadline>),
+    Quit,
+}
+
+impl std::str::FromStr for Request {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (command, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let rest = rest.trim();
+        let timer = (!rest.is_empty()).then(|| rest.to_string());
+        Ok(match command {
+            "status" => Request::Status,
+            "start" => Request::Start(timer),
+            "pause" => Request::Pause(timer),
+            "toggle" => Request::Toggle(timer),
+            "reset" => Request::Reset(timer),
+            "adjust" => {
+                let (step, timer) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                let timer = timer.trim();
+                let timer = (!timer.is_empty()).then(|| timer.to_string());
+                match step.strip_prefix('-') {
+                    Some(step) => Request::Decrement(timer, duration::parse_duration(step)?),
+                    None => {
+                        let step = step.strip_prefix('+').unwrap_or(step);
+                        Request::Increment(timer, duration::parse_duration(step)?)
+                    }
+                }
+            }
+            "task" => Request::SetTask(rest.to_string()),
+            "add-stopwatch" => Request::AddStopwatch(rest.to_string()),
+            "add-countdown" => {
+                let mut countdown = Labeled::<Duration>::default();
+                countdown.from_string(rest.to_string())?;
+                Request::AddCountdown(countdown)
+            }
+            "add-deadline" => {
+                let mut deadline = Labeled::<Deadline>::default();
+                deadline.from_string(rest.to_string())?;
+                Request::AddDeadline(deadline)
+            }
+            "quit" => Request::Quit,
+            "" => return Err("empty command".to_string()),
+            command => return Err(format!("unknown command {command:?}")),
+        })
+    }
+}
+
+/// Where the reply to a request goes.
+pub type Reply = Sender<String>;
+
+/// The error reply for a request that couldn't be carried out.
+pub fn error(err: &str) -> String {
+    format!("error\t{}\n", err.replace(['\t', '\n'], " "))
+}
+
+/// The reply to every request that went through: `ok`, then a line per
+/// timer with its fields separated by tabs and any name last.
+///
+/// - `active`, the number of the active stopwatch.
+/// - `stopwatch`, `running` or `paused`, its elapsed ms, its name and its
+///   task.
+/// - `countdown`, `running`, `paused` or `finished`, its remaining ms and its
+///   label.
+/// - `deadline`, the seconds until it is next due (negative once it has
+///   passed, `-` if it never is again), then `label=deadline`.
+pub fn status(
+    active_stopwatch: usize,
+    stopwatches: &[Stopwatch],
+    countdowns: &[Labeled<Countdown>],
+    deadlines: &[Labeled<Deadline>],
+    now: DateTime<Local>,
+) -> String {
+    let clean = |s: &str| s.replace(['\t', '\n'], " ");
+    let mut out = String::from("ok\n");
+    out.push_str(&format!("active\t{}\n", active_stopwatch + 1));
+    for stopwatch in stopwatches {
+        let state = if stopwatch.is_running() {
+            "running"
+        } else {
+            "paused"
+        };
+        out.push_str(&format!(
+            "stopwatch\t{state}\t{}\t{}\t{}\n",
+            stopwatch.elapsed().as_millis(),
+            clean(&stopwatch.name),
+            clean(&stopwatch.task),
+        ));
+    }
+    for countdown in countdowns {
+        let state = match &countdown.value {
+            countdown if countdown.is_finished() => "finished",
+            countdown if countdown.is_running() => "running",
+            _ => "paused",
+        };
+        out.push_str(&format!(
+            "countdown\t{state}\t{}\t{}\n",
+            countdown.value.remaining().as_millis(),
+            clean(&countdown.label),
+        ));
+    }
+    for deadline in deadlines {
+        let due = match deadline.value.next(now) {
+            Some(next) => next.signed_duration_since(now).num_seconds().to_string(),
+            None => "-".to_string(),
+        };
+        out.push_str(&format!(
+            "deadline\t{due}\t{}\n",
+            clean(&deadline.to_string())
+        ));
+    }
+    out
+}
+
+/// Listens on a Unix socket for line based [`Request`]s. Each reply is sent
+/// back as lines ending with an empty one. Connections are served on their
+/// own threads; the requests wait in a queue until the main loop picks them
+/// up with [`next_request`](Self::next_request).
+#[derive(Debug)]
+pub struct Control {
+    path: PathBuf,
+    requests: Receiver<(Request, Reply)>,
+}
+
+impl Control {
+    /// Starts listening at `path`. A socket left behind by an instance that
+    /// is gone is replaced, one that still answers is an error.
+    pub fn listen(path: &Path) -> io::Result<Self> {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is in use by another instance", path.display()),
+            ));
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                std::thread::spawn(move || serve(stream, sender));
+            }
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            requests,
+        })
+    }
+
+    /// The oldest request that hasn't been picked up yet, if any.
+    pub fn next_request(&self) -> Option<(Request, Reply)> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answers the requests of one connection until it closes or the main loop
+/// goes away.
+fn serve(stream: UnixStream, requests: Sender<(Request, Reply)>) {
+    let Ok(mut out) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match line.parse::<Request>() {
+            Ok(request) => {
+                let (sender, reply) = mpsc::channel();
+                if requests.send((request, sender)).is_err() {
+                    return;
+                }
+                match reply.recv() {
+                    Ok(reply) => reply,
+                    Err(_) => return,
+                }
+            }
+            Err(err) => error(&err),
+        };
+        if writeln!(out, "{reply}").is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::stopwatch::Stopwatch;
+
+/// Counts down from `duration`. The time already spent is tracked with a
+/// [`Stopwatch`] so pausing and resuming work the same way.
+#[derive(Debug, Clone, Default)]
+pub struct Countdown {
+    pub duration: Duration,
+    pub stopwatch: Stopwatch,
+}
+
+impl Countdown {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.stopwatch.is_running() && !self.is_finished()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stopwatch.elapsed() >= self.duration
+    }
+
+    /// Pauses or resumes the countdown. A finished countdown starts over.
+    pub fn toggle(&mut self) {
+        if self.is_finished() {
+            self.restart();
+        } else {
+            self.stopwatch.toggle();
+        }
+    }
+
+    /// Stops the clock of a countdown that has run out. Returns whether it
+    /// was still ticking.
+    pub fn finish(&mut self) -> bool {
+        let ticking = self.is_finished() && self.stopwatch.is_running();
+        if ticking {
+            self.stopwatch.toggle();
+        }
+        ticking
+    }
+
+    pub fn restart(&mut self) {
+        self.stopwatch.clear();
+        self.stopwatch.toggle();
+    }
+
+    /// Makes the countdown `step` longer, also for later restarts.
+    pub fn extend(&mut self, step: Duration) {
+        self.duration += step;
+    }
+
+    pub fn shorten(&mut self, step: Duration) {
+        self.duration = self.duration.saturating_sub(step);
+    }
+
+    /// Changes the length so that `remaining` is left from now on.
+    pub fn set_remaining(&mut self, remaining: Duration) {
+        self.duration = self.stopwatch.elapsed() + remaining;
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.stopwatch.elapsed())
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::control;
+
+/// How long to wait for the running instance to answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The `status` lines of a reply as one JSON object, with `active`,
+/// `stopwatches`, `countdowns` and `deadlines`.
+fn to_json(lines: &[String]) -> Result<String, String> {
+    let mut active = "null".to_string();
+    let (mut stopwatches, mut countdowns, mut deadlines) = (vec![], vec![], vec![]);
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields[..] {
+            ["active", number] => active = number.to_string(),
+            ["stopwatch", state, elapsed, name, task] => stopwatches.push(format!(
+                "{{\"name\":{},\"task\":{},\"running\":{},\"elapsed_ms\":{elapsed}}}",
+                json_string(name),
+                json_string(task),
+                state == "running",
+            )),
+            ["countdown", state, remaining, label] => countdowns.push(format!(
+                "{{\"label\":{},\"state\":{},\"remaining_ms\":{remaining}}}",
+                json_string(label),
+                json_string(state),
+            )),
+            ["deadline", due, deadline] => {
+                let (label, deadline) = deadline.split_once('=').unwrap_or(("", deadline));
+                let (relative, deadline) = match deadline.strip_prefix('~') {
+                    Some(deadline) => (true, deadline),
+                    None => (false, deadline),
+                };
+                let due = if due == "-" { "null" } else { due };
+                deadlines.push(format!(
+                    "{{\"label\":{},\"deadline\":{},\"relative\":{relative},\"due_in_s\":{due}}}",
+                    json_string(label),
+                    json_string(deadline),
+                ));
+            }
+            _ => return Err(format!("unexpected reply {line:?}")),
+        }
+    }
+    Ok(format!(
+        "{{\"active\":{active},\"stopwatches\":[{}],\"countdowns\":[{}],\"deadlines\":[{}]}}",
+        stopwatches.join(","),
+        countdowns.join(","),
+        deadlines.join(","),
+    ))
+}
+
+/// Sends `request` to the instance listening at `path` and returns the lines
+/// of its reply.
+pub fn send(path: &std::path::Path, request: &str) -> Result<Vec<String>, String> {
+    let stream = UnixStream::connect(path)
+        .map_err(|err| format!("no running instance at {}: {err}", path.display()))?;
+    let err = |err: std::io::Error| format!("{}: {err}", path.display());
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(err)?;
+    writeln!(&stream, "{request}").map_err(err)?;
+    let mut lines = vec![];
+    for line in BufReader::new(&stream).lines() {
+        let line = line.map_err(err)?;
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    match lines.first().map(String::as_str) {
+        Some("ok") => Ok(lines.split_off(1)),
+        Some(line) => match line.strip_prefix("error\t") {
+            Some(message) => Err(message.to_string()),
+            None => Err(format!("unexpected reply {line:?}")),
+        },
+        None => Err("the instance closed the connection without a reply".to_string()),
+    }
+}
+
+/// `osd-timer ctl [--json] [--control_socket PATH] COMMAND...`
+///
+/// Sends one [`control::Request`] to the running instance and prints the
+/// state it replies with, as tab separated lines or as JSON.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut json = false;
+    let mut path = None;
+    let mut words = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--control_socket" => {
+                let socket = args.next().ok_or("Missing arg for control_socket")?;
+                path = Some(PathBuf::from(socket));
+            }
+            _ => words.push(arg),
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => control::default_path().ok_or("no socket, set --control_socket")?,
+    };
+    let request = if words.is_empty() {
+        "status".to_string()
+    } else {
+        words.join(" ")
+    };
+    // Checked here too, so a typo doesn't need a running instance to be caught.
+    request.parse::<control::Request>()?;
+    let lines = send(&path, &request)?;
+    if json {
+        println!("{}", to_json(&lines)?);
+    } else {
+        for line in lines {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
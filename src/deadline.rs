@@ -0,0 +1,359 @@
+use chrono::{
+    DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Weekday,
+};
+
+use crate::duration::parse_duration;
+use crate::schedule::Schedule;
+
+/// What happens to a one-off deadline once it has passed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Expired {
+    /// Keep counting up how late we are.
+    #[default]
+    Keep,
+    /// Keep the cell, but leave it empty.
+    Hide,
+    /// Drop the cell from the grid.
+    Remove,
+}
+
+impl std::str::FromStr for Expired {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Expired::Keep),
+            "hide" => Ok(Expired::Hide),
+            "remove" => Ok(Expired::Remove),
+            _ => Err(format!("expected keep, hide or remove, got {s:?}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Deadline {
+    At(DateTime<Local>),
+    /// A deadline with a known start, from `start..end`.
+    Between(DateTime<Local>, DateTime<Local>),
+    Recurring(Schedule),
+}
+
+impl Default for Deadline {
+    fn default() -> Self {
+        Deadline::At(Default::default())
+    }
+}
+
+/// Writes the deadline in a form that parses back to the same deadline.
+impl std::fmt::Display for Deadline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rfc3339 = |at: &DateTime<Local>| at.to_rfc3339_opts(SecondsFormat::AutoSi, false);
+        match self {
+            Deadline::At(at) => f.write_str(&rfc3339(at)),
+            Deadline::Between(start, end) => write!(f, "{}..{}", rfc3339(start), rfc3339(end)),
+            Deadline::Recurring(schedule) => schedule.fmt(f),
+        }
+    }
+}
+
+impl Deadline {
+    /// The point in time to count down to. Recurring deadlines roll over to
+    /// their next occurrence as soon as the current one has passed.
+    pub fn next(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Deadline::At(at) | Deadline::Between(_, at) => Some(*at),
+            Deadline::Recurring(schedule) => schedule.next_after(now),
+        }
+    }
+
+    /// How far `now` is through the deadline's window, from 0.0 to 1.0. Only
+    /// deadlines with a known start have one.
+    pub fn progress(&self, now: DateTime<Local>) -> Option<f32> {
+        let Deadline::Between(start, end) = self else {
+            return None;
+        };
+        let total = end.signed_duration_since(*start).num_milliseconds();
+        let done = now.signed_duration_since(*start).num_milliseconds();
+        Some((done as f32 / total.max(1) as f32).clamp(0.0, 1.0))
+    }
+
+    /// Moves a one-off deadline by `by`, which may be negative. Windows keep
+    /// their start. Recurring deadlines stay put, and so does one that would
+    /// end up past what a date can hold.
+    pub fn shift(&mut self, by: chrono::Duration) -> Result<(), &'static str> {
+        match self {
+            Deadline::At(at) | Deadline::Between(_, at) => {
+                *at = at
+                    .checked_add_signed(by)
+                    .ok_or("can't move a deadline that far")?;
+                Ok(())
+            }
+            Deadline::Recurring(_) => Err("recurring deadlines can't be moved"),
+        }
+    }
+
+    /// Whether a one-off deadline passed at least `grace` ago. Recurring
+    /// deadlines never expire.
+    pub fn is_expired(&self, now: DateTime<Local>, grace: std::time::Duration) -> bool {
+        match self {
+            Deadline::At(at) | Deadline::Between(_, at) => now
+                .signed_duration_since(*at)
+                .to_std()
+                .is_ok_and(|overdue| overdue >= grace),
+            Deadline::Recurring(_) => false,
+        }
+    }
+}
+
+/// Parses `start..end`, where both ends are anything [`parse_datetime`]
+/// accepts. A start that rolled over past the end is moved back a day at a
+/// time, so `10:00..18:00` still means today's window at noon.
+pub fn parse_window(
+    s: &str,
+    now: DateTime<Local>,
+) -> Result<(DateTime<Local>, DateTime<Local>), String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("expected start..end, got {s:?}"))?;
+    let end = parse_datetime(end, now)?;
+    let mut start = parse_datetime(start, now)?;
+    for _ in 0..7 {
+        if start < end {
+            return Ok((start, end));
+        }
+        let Some(earlier) = start.checked_sub_days(Days::new(1)) else {
+            break;
+        };
+        start = earlier;
+    }
+    Err(format!("{s:?} starts after it ends"))
+}
+
+const ACCEPTED_FORMS: &str = "RFC 3339 (2024-05-01T17:00:00+02:00), \
+    2024-05-01T17:00:00, 2024-05-01, 17:00[:ss], 9am, 9:30pm, eod, noon, midnight, \
+    in 45m, +2h30m, today|tomorrow|yesterday [time], [next] <weekday> [time]";
+
+/// Parses an absolute or relative point in time such as `17:00`, `in 45m`,
+/// `+2h30m`, `tomorrow 9am`, `next fri 17:00` or `eod`.
+///
+/// A bare time (or weekday) that has already passed rolls over to the next
+/// day (or week).
+pub fn parse_datetime(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    parse_datetime_toward(s, now, false)
+}
+
+/// Like [`parse_datetime`], but for looking back: a bare time (or weekday)
+/// that is still ahead means the last one, so `09:00` at noon is this
+/// morning and `mon` on a Wednesday is two days ago.
+pub fn parse_past_datetime(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    parse_datetime_toward(s, now, true)
+}
+
+fn parse_datetime_toward(
+    s: &str,
+    now: DateTime<Local>,
+    past: bool,
+) -> Result<DateTime<Local>, String> {
+    let s = s.trim();
+    if let Ok(at) = s.parse::<DateTime<Local>>() {
+        return Ok(at);
+    }
+    if let Ok(at) = s.parse::<NaiveDateTime>() {
+        return at
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or_else(|| format!("{s:?} does not exist in the local timezone"));
+    }
+    if let Ok(date) = s.parse::<NaiveDate>() {
+        return date
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or_else(|| format!("{s:?} does not exist in the local timezone"));
+    }
+    let lower = s.to_ascii_lowercase();
+    if let Some(duration) = lower
+        .strip_prefix("in ")
+        .or_else(|| lower.strip_prefix('+'))
+    {
+        if let Ok(duration) = parse_duration(duration) {
+            return after(now, duration).ok_or_else(|| format!("{s:?} is too far ahead"));
+        }
+    }
+    parse_relative(&lower, now, past).ok_or_else(|| {
+        format!("can't parse {s:?} as a deadline, expected one of: {ACCEPTED_FORMS}")
+    })
+}
+
+/// `duration` after `now`, or `None` if that is past what a date can hold.
+pub fn after(now: DateTime<Local>, duration: std::time::Duration) -> Option<DateTime<Local>> {
+    now.checked_add_signed(chrono::Duration::from_std(duration).ok()?)
+}
+
+fn parse_relative(s: &str, now: DateTime<Local>, past: bool) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    let words: Vec<&str> = s.split_whitespace().collect();
+    // How far to move the result if it ends up on the wrong side of `now`.
+    let (date, rest, roll_over) = match words[..] {
+        ["today", ref rest @ ..] => (today, rest, None),
+        ["tomorrow", ref rest @ ..] => (today.succ_opt()?, rest, None),
+        ["yesterday", ref rest @ ..] => (today.pred_opt()?, rest, None),
+        ["next", day, ref rest @ ..] => {
+            let days_ahead = match days_until(today, parse_weekday(day)?) {
+                0 => 7,
+                days_ahead => days_ahead,
+            };
+            (today + Days::new(days_ahead as u64), rest, None)
+        }
+        [day, ref rest @ ..] if parse_weekday(day).is_some() => {
+            let days_ahead = days_until(today, parse_weekday(day)?);
+            let date = if past && days_ahead > 0 {
+                today + Days::new(days_ahead as u64) - Days::new(7)
+            } else {
+                today + Days::new(days_ahead as u64)
+            };
+            (date, rest, Some(Days::new(7)))
+        }
+        [] => return None,
+        ref rest => (today, rest, Some(Days::new(1))),
+    };
+    let time = match rest {
+        [] => NaiveTime::MIN,
+        // Allows a space before am/pm.
+        rest => parse_time(&rest.concat())?,
+    };
+    let at = date.and_time(time).and_local_timezone(Local).earliest()?;
+    let date = match roll_over {
+        Some(days) if past && at > now => date - days,
+        Some(days) if !past && at <= now => date + days,
+        _ => return Some(at),
+    };
+    date.and_time(time).and_local_timezone(Local).earliest()
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    const DAYS: [(&str, Weekday); 7] = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    if s.len() < 3 {
+        return None;
+    }
+    DAYS.iter()
+        .find(|(name, _)| name.starts_with(s))
+        .map(|(_, day)| *day)
+}
+
+fn days_until(from: NaiveDate, day: Weekday) -> u32 {
+    (day.num_days_from_monday() + 7 - from.weekday().num_days_from_monday()) % 7
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    match s {
+        "eod" => return NaiveTime::from_hms_opt(17, 0, 0),
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => (),
+    }
+    let (clock, pm) = match (s.strip_suffix("am"), s.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, false),
+        (_, Some(clock)) => (clock, true),
+        _ => return s.parse::<NaiveTime>().ok(),
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::tests::at;
+
+    /// Wednesday 2024-01-10, 12:00.
+    fn now() -> DateTime<Local> {
+        at(2024, 1, 10, 12, 0)
+    }
+
+    fn parse(s: &str) -> DateTime<Local> {
+        parse_datetime(s, now()).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[test]
+    fn absolute_forms() {
+        assert_eq!(parse("2024-05-01T17:00:00"), at(2024, 5, 1, 17, 0));
+        assert_eq!(parse("2024-05-01"), at(2024, 5, 1, 0, 0));
+        let rfc3339 = "2024-05-01T17:00:00+02:00";
+        assert_eq!(parse(rfc3339), rfc3339.parse::<DateTime<Local>>().unwrap());
+    }
+
+    #[test]
+    fn relative_forms() {
+        assert_eq!(parse("in 45m"), at(2024, 1, 10, 12, 45));
+        assert_eq!(parse("+2h30m"), at(2024, 1, 10, 14, 30));
+        assert_eq!(parse("tomorrow 9am"), at(2024, 1, 11, 9, 0));
+        assert_eq!(parse("yesterday noon"), at(2024, 1, 9, 12, 0));
+        assert_eq!(parse("today eod"), at(2024, 1, 10, 17, 0));
+        assert_eq!(parse("Fri 17:00"), at(2024, 1, 12, 17, 0));
+        assert_eq!(parse("next friday"), at(2024, 1, 12, 0, 0));
+        assert_eq!(parse("9:30 pm"), at(2024, 1, 10, 21, 30));
+        assert_eq!(parse("12am"), at(2024, 1, 11, 0, 0));
+    }
+
+    #[test]
+    fn times_that_passed_roll_over() {
+        assert_eq!(parse("17:00"), at(2024, 1, 10, 17, 0));
+        assert_eq!(parse("09:30"), at(2024, 1, 11, 9, 30));
+        assert_eq!(parse("noon"), at(2024, 1, 11, 12, 0));
+        // Today is a Wednesday.
+        assert_eq!(parse("wed 13:00"), at(2024, 1, 10, 13, 0));
+        assert_eq!(parse("wed 9am"), at(2024, 1, 17, 9, 0));
+        assert_eq!(parse("next wed 13:00"), at(2024, 1, 17, 13, 0));
+        // Explicit days don't roll over.
+        assert_eq!(parse("today 9am"), at(2024, 1, 10, 9, 0));
+    }
+
+    #[test]
+    fn past_times_go_back_instead() {
+        let past = |s: &str| parse_past_datetime(s, now()).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(past("09:30"), at(2024, 1, 10, 9, 30));
+        assert_eq!(past("17:00"), at(2024, 1, 9, 17, 0));
+        assert_eq!(past("noon"), at(2024, 1, 10, 12, 0));
+        // Today is a Wednesday.
+        assert_eq!(past("mon"), at(2024, 1, 8, 0, 0));
+        assert_eq!(past("wed 9am"), at(2024, 1, 10, 9, 0));
+        assert_eq!(past("wed 13:00"), at(2024, 1, 3, 13, 0));
+        assert_eq!(past("fri"), at(2024, 1, 5, 0, 0));
+        // Explicit dates are left alone.
+        assert_eq!(past("tomorrow 9am"), at(2024, 1, 11, 9, 0));
+        assert_eq!(past("2024-05-01"), at(2024, 5, 1, 0, 0));
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        assert!(parse_datetime("soon", now()).is_err());
+        assert!(parse_datetime("13pm", now()).is_err());
+        assert!(parse_datetime("in 99999999w", now())
+            .unwrap_err()
+            .contains("too far ahead"));
+    }
+
+    #[test]
+    fn windows_start_before_they_end() {
+        assert_eq!(
+            parse_window("10:00..18:00", now()),
+            Ok((at(2024, 1, 10, 10, 0), at(2024, 1, 10, 18, 0)))
+        );
+        assert!(parse_window("18:00", now()).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+const UNITS: &[(&str, u64)] = &[
+    ("ms", 1),
+    ("w", 7 * 24 * 3600 * 1000),
+    ("d", 24 * 3600 * 1000),
+    ("h", 3600 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+];
+
+/// Parses a duration such as `25m`, `1h30m`, `1h 30m 10s`, `90` (seconds) or
+/// a clock-style `[[hh:]mm:]ss`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if s.contains(':') {
+        let mut secs = 0u64;
+        for part in s.split(':') {
+            let part = part
+                .parse::<u64>()
+                .map_err(|err| format!("{part:?} in {s:?}: {err}"))?;
+            secs = secs
+                .checked_mul(60)
+                .and_then(|secs| secs.checked_add(part))
+                .ok_or_else(|| format!("{s:?} is too long"))?;
+        }
+        return Ok(Duration::from_secs(secs));
+    }
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut millis = 0u64;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!("expected a number at {rest:?} in {s:?}"));
+        }
+        let value = rest[..digits]
+            .parse::<u64>()
+            .map_err(|err| format!("{err}"))?;
+        rest = &rest[digits..];
+        let Some((unit, scale)) = UNITS.iter().find(|(unit, _)| rest.starts_with(unit)) else {
+            return Err(format!(
+                "expected one of w, d, h, m, s, ms at {rest:?} in {s:?}"
+            ));
+        };
+        millis = value
+            .checked_mul(*scale)
+            .and_then(|value| millis.checked_add(value))
+            .ok_or_else(|| format!("{s:?} is too long"))?;
+        rest = rest[unit.len()..].trim_start();
+    }
+    Ok(Duration::from_millis(millis))
+}
@@ -1,4 +1,7 @@
 use std::fmt::{self, Display};
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, Months};
 
 pub struct FmtFn<F>(pub F)
 where
@@ -74,3 +77,460 @@ impl<T: Display> OrDisplayExt<T> for Option<T> {
         OrDisplay(self, u)
     }
 }
+
+/// Displays a signed number of seconds as rounded, human phrasing such as
+/// "in about 3 hours", "in 12 minutes" or "5 minutes ago".
+#[derive(Debug, Clone, Copy)]
+pub struct Relative(pub i64);
+
+impl Display for Relative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MINUTE: i64 = 60;
+        const HOUR: i64 = 60 * MINUTE;
+        const DAY: i64 = 24 * HOUR;
+        if self.0 == 0 {
+            return f.write_str("now");
+        }
+        let secs = self.0.abs();
+        let rounded = |unit: i64| (secs + unit / 2) / unit;
+        let future = self.0 > 0;
+        if future {
+            f.write_str("in ")?;
+        }
+        match secs {
+            s if s < 45 => f.write_str("a few seconds"),
+            s if s < 90 => f.write_str("a minute"),
+            s if s < 45 * MINUTE => write!(f, "{} minutes", rounded(MINUTE)),
+            s if s < 90 * MINUTE => f.write_str("about an hour"),
+            s if s < 22 * HOUR => write!(f, "about {} hours", rounded(HOUR)),
+            s if s < 36 * HOUR => f.write_str("a day"),
+            s if s < 26 * DAY => write!(f, "{} days", rounded(DAY)),
+            s if s < 45 * DAY => f.write_str("about a month"),
+            s if s < 320 * DAY => write!(f, "{} months", rounded(30 * DAY)),
+            s if s < 548 * DAY => f.write_str("about a year"),
+            _ => write!(f, "{} years", rounded(365 * DAY)),
+        }?;
+        if !future {
+            f.write_str(" ago")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Unit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+    Months,
+}
+
+impl Unit {
+    fn secs(self) -> u64 {
+        match self {
+            Unit::Seconds => 1,
+            Unit::Minutes => 60,
+            Unit::Hours => 3600,
+            Unit::Days => 24 * 3600,
+            Unit::Weeks => 7 * 24 * 3600,
+            // Only used for plain durations, see `display_between`.
+            Unit::Months => 30 * 24 * 3600,
+        }
+    }
+
+    fn default_width(self) -> usize {
+        match self {
+            Unit::Seconds | Unit::Minutes | Unit::Hours => 2,
+            Unit::Days | Unit::Weeks | Unit::Months => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Literal(String),
+    Sign,
+    Field {
+        unit: Unit,
+        width: usize,
+        /// Hide the field and the literal after it while it and every larger
+        /// unit are zero.
+        hide_zero: bool,
+    },
+    Fraction(u32),
+}
+
+/// A strftime-like pattern for durations, e.g. `%H:%M:%S.%3f` or `%-dd %Hh`.
+///
+/// - `%m`, `%w`, `%d`, `%H`, `%M`, `%S`: months, weeks, days, hours, minutes,
+///   seconds. The largest unit in the pattern takes everything that doesn't
+///   fit in it, so `%M:%S` shows 2h as `120:00`.
+/// - `%3f`: the fraction of a second with that many digits (default 3).
+/// - `%+`: `+` or `-`. Without it negative values start with `-`.
+/// - `%%`: a literal `%`.
+///
+/// Flags go between the `%` and the unit: `-` drops the zero padding, `#`
+/// hides the unit (and the text after it) while it is a leading zero.
+///
+/// With `adaptive_precision` set, seconds and fractions are left out of
+/// anything at least a day long.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DurationFormat {
+    items: Vec<Item>,
+    pub adaptive_precision: bool,
+}
+
+impl std::str::FromStr for DurationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut items = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let mut pad = true;
+            let mut hide_zero = false;
+            let mut digits = String::new();
+            let spec = loop {
+                match chars.next() {
+                    Some('-') => pad = false,
+                    Some('#') => hide_zero = true,
+                    Some(c @ '0'..='9') => digits.push(c),
+                    Some(c) => break c,
+                    None => return Err(format!("{s:?} ends in the middle of a field")),
+                }
+            };
+            if spec == '%' {
+                literal.push('%');
+                continue;
+            }
+            if !literal.is_empty() {
+                items.push(Item::Literal(std::mem::take(&mut literal)));
+            }
+            let unit = match spec {
+                '+' => {
+                    items.push(Item::Sign);
+                    continue;
+                }
+                'f' => {
+                    let precision = if digits.is_empty() {
+                        3
+                    } else {
+                        digits.parse().map_err(|err| format!("{err}"))?
+                    };
+                    if !(1..=9).contains(&precision) {
+                        return Err(format!("%{precision}f needs 1 to 9 digits"));
+                    }
+                    items.push(Item::Fraction(precision));
+                    continue;
+                }
+                'm' => Unit::Months,
+                'w' => Unit::Weeks,
+                'd' => Unit::Days,
+                'H' => Unit::Hours,
+                'M' => Unit::Minutes,
+                'S' => Unit::Seconds,
+                c => return Err(format!("unknown field %{c} in {s:?}")),
+            };
+            items.push(Item::Field {
+                unit,
+                width: if pad { unit.default_width() } else { 0 },
+                hide_zero,
+            });
+        }
+        if !literal.is_empty() {
+            items.push(Item::Literal(literal));
+        }
+        Ok(Self {
+            items,
+            adaptive_precision: false,
+        })
+    }
+}
+
+/// A duration split over the units of a [`DurationFormat`].
+struct Split {
+    values: [u64; 6],
+    nanos: u32,
+    negative: bool,
+    /// At least a day long.
+    long: bool,
+}
+
+impl DurationFormat {
+    /// The units in the pattern, largest first.
+    fn units(&self) -> Vec<Unit> {
+        let mut units: Vec<Unit> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Field { unit, .. } => Some(*unit),
+                _ => None,
+            })
+            .collect();
+        units.sort_unstable_by(|a, b| b.cmp(a));
+        units.dedup();
+        units
+    }
+
+    /// Formats `duration`, which is taken as negative if `negative` is set.
+    pub fn display(&self, duration: Duration, negative: bool) -> impl Display + '_ {
+        FmtFn(move |f| self.write(f, self.split(duration, negative)))
+    }
+
+    /// Formats the time from `from` until `to`, negative if `to` is earlier.
+    ///
+    /// Months, weeks and days are counted on the calendar, so a day is
+    /// whatever it takes to reach the same wall-clock time on the next date,
+    /// including across DST changes.
+    pub fn display_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> impl Display + '_ {
+        FmtFn(move |f| self.write(f, self.split_between(from, to)))
+    }
+
+    fn split(&self, duration: Duration, negative: bool) -> Split {
+        let mut values = [0u64; 6];
+        let mut secs = duration.as_secs();
+        for unit in self.units() {
+            values[unit as usize] = secs / unit.secs();
+            secs %= unit.secs();
+        }
+        Split {
+            values,
+            nanos: duration.subsec_nanos(),
+            negative,
+            long: duration.as_secs() >= Unit::Days.secs(),
+        }
+    }
+
+    fn split_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Split {
+        let negative = to < from;
+        let (from, to) = if negative { (to, from) } else { (from, to) };
+        let units = self.units();
+        let mut values = [0u64; 6];
+        let mut cursor = from;
+        if units.contains(&Unit::Months) {
+            let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+            for months in (0..=months.max(0) as u32).rev() {
+                match from.checked_add_months(Months::new(months)) {
+                    Some(at) if at <= to => {
+                        values[Unit::Months as usize] = months as u64;
+                        cursor = at;
+                        break;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        if units.contains(&Unit::Weeks) || units.contains(&Unit::Days) {
+            let mut days = (to.date_naive() - cursor.date_naive()).num_days().max(0) as u64;
+            while days > 0
+                && cursor
+                    .checked_add_days(Days::new(days))
+                    .is_none_or(|at| at > to)
+            {
+                days -= 1;
+            }
+            let weeks = if units.contains(&Unit::Weeks) {
+                days / 7
+            } else {
+                0
+            };
+            values[Unit::Weeks as usize] = weeks;
+            // Without a day unit the leftover days go to the smaller units.
+            if units.contains(&Unit::Days) {
+                values[Unit::Days as usize] = days - weeks * 7;
+            } else {
+                days = weeks * 7;
+            }
+            cursor = cursor.checked_add_days(Days::new(days)).unwrap_or(cursor);
+        }
+        let rest = to
+            .signed_duration_since(cursor)
+            .to_std()
+            .unwrap_or_default();
+        let mut secs = rest.as_secs();
+        for unit in units.into_iter().filter(|unit| *unit < Unit::Days) {
+            values[unit as usize] = secs / unit.secs();
+            secs %= unit.secs();
+        }
+        Split {
+            values,
+            nanos: rest.subsec_nanos(),
+            negative,
+            long: to.signed_duration_since(from) >= chrono::Duration::days(1),
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, split: Split) -> fmt::Result {
+        if !(self.adaptive_precision && split.long) {
+            return self.write_items(f, &split, false);
+        }
+        let mut s = String::new();
+        self.write_items(&mut s, &split, true)?;
+        f.write_str(s.trim_end_matches(|c: char| !c.is_alphanumeric()))
+    }
+
+    fn write_items(
+        &self,
+        f: &mut impl fmt::Write,
+        split: &Split,
+        drop_seconds: bool,
+    ) -> fmt::Result {
+        if split.negative && !self.items.contains(&Item::Sign) {
+            f.write_str("-")?;
+        }
+        // Whether every unit written so far was zero (and hidden if it could be).
+        let mut leading = true;
+        let mut skip_literal = false;
+        for item in &self.items {
+            match item {
+                Item::Literal(s) => {
+                    if !std::mem::take(&mut skip_literal) {
+                        f.write_str(s)?;
+                    }
+                }
+                Item::Sign => {
+                    skip_literal = false;
+                    f.write_str(if split.negative { "-" } else { "+" })?;
+                }
+                Item::Field {
+                    unit: Unit::Seconds,
+                    ..
+                }
+                | Item::Fraction(_)
+                    if drop_seconds =>
+                {
+                    skip_literal = true;
+                }
+                Item::Field {
+                    unit,
+                    width,
+                    hide_zero,
+                } => {
+                    let value = split.values[*unit as usize];
+                    leading &= value == 0;
+                    skip_literal = leading && *hide_zero;
+                    if !skip_literal {
+                        write!(f, "{value:0width$}")?;
+                    }
+                }
+                Item::Fraction(precision) => {
+                    skip_literal = false;
+                    let fraction = split.nanos / 10u32.pow(9 - precision);
+                    write!(f, "{fraction:0width$}", width = *precision as usize)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn format(pattern: &str, duration: Duration) -> String {
+        let format: DurationFormat = pattern.parse().unwrap();
+        let formatted = format.display(duration, false).to_string();
+        formatted
+    }
+
+    const DAY: u64 = 24 * 3600;
+
+    #[test]
+    fn pads_fields_by_default() {
+        let duration = Duration::from_millis(3_723_045);
+        assert_eq!(format("%H:%M:%S.%3f", duration), "01:02:03.045");
+        assert_eq!(format("%H:%M:%S.%f", duration), "01:02:03.045");
+        assert_eq!(format("%H:%M:%S.%1f", duration), "01:02:03.0");
+        assert_eq!(format("%-Hh %-Mm %-Ss", duration), "1h 2m 3s");
+    }
+
+    #[test]
+    fn largest_unit_takes_the_rest() {
+        assert_eq!(format("%M:%S", Duration::from_secs(7200)), "120:00");
+        assert_eq!(
+            format("%-dd %H:%M", Duration::from_secs(9 * DAY)),
+            "9d 00:00"
+        );
+        assert_eq!(format("%-ww %-dd", Duration::from_secs(9 * DAY)), "1w 2d");
+    }
+
+    #[test]
+    fn hash_hides_leading_zero_fields() {
+        let pattern = "%#-Hh %#-Mm %-S.%3fs";
+        assert_eq!(format(pattern, Duration::from_millis(5_250)), "5.250s");
+        assert_eq!(format(pattern, Duration::from_secs(65)), "1m 5.000s");
+        // Zero fields after a non-zero one stay.
+        assert_eq!(format(pattern, Duration::from_secs(3605)), "1h 0m 5.000s");
+    }
+
+    #[test]
+    fn signs() {
+        let format: DurationFormat = "%M:%S".parse().unwrap();
+        assert_eq!(
+            format.display(Duration::from_secs(61), true).to_string(),
+            "-01:01"
+        );
+        let format: DurationFormat = "%+%M:%S".parse().unwrap();
+        assert_eq!(
+            format.display(Duration::from_secs(61), false).to_string(),
+            "+01:01"
+        );
+        assert_eq!(
+            format.display(Duration::from_secs(61), true).to_string(),
+            "-01:01"
+        );
+        assert_eq!(self::format("100%% %S", Duration::from_secs(1)), "100% 01");
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        assert!("%H:%".parse::<DurationFormat>().is_err());
+        assert!("%Q".parse::<DurationFormat>().is_err());
+        assert!("%0f".parse::<DurationFormat>().is_err());
+        assert!("%10f".parse::<DurationFormat>().is_err());
+    }
+
+    #[test]
+    fn adaptive_precision_drops_seconds_from_long_durations() {
+        let mut format: DurationFormat = "%#-dd %#-Hh %#-Mm %-Ss".parse().unwrap();
+        format.adaptive_precision = true;
+        let long = Duration::from_secs(2 * DAY + 3 * 3600 + 4 * 60 + 5);
+        assert_eq!(format.display(long, false).to_string(), "2d 3h 4m");
+        let short = Duration::from_secs(3 * 3600 + 4 * 60 + 5);
+        assert_eq!(format.display(short, false).to_string(), "3h 4m 5s");
+        format.adaptive_precision = false;
+        assert_eq!(format.display(long, false).to_string(), "2d 3h 4m 5s");
+    }
+
+    #[test]
+    fn calendar_units_between_dates() {
+        let format: DurationFormat = "%-mmo %-dd %H:%M".parse().unwrap();
+        let from = Local.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        let to = Local.with_ymd_and_hms(2024, 3, 2, 13, 30, 0).unwrap();
+        assert_eq!(format.display_between(from, to).to_string(), "1mo 2d 01:30");
+        assert_eq!(
+            format.display_between(to, from).to_string(),
+            "-1mo 2d 01:30"
+        );
+    }
+
+    #[test]
+    fn relative_phrasing() {
+        assert_eq!(Relative(0).to_string(), "now");
+        assert_eq!(Relative(30).to_string(), "in a few seconds");
+        assert_eq!(Relative(12 * 60).to_string(), "in 12 minutes");
+        assert_eq!(Relative(3 * 3600 + 1200).to_string(), "in about 3 hours");
+        assert_eq!(Relative(-5 * 60).to_string(), "5 minutes ago");
+        assert_eq!(Relative(-2 * DAY as i64).to_string(), "2 days ago");
+    }
+}
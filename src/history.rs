@@ -0,0 +1,231 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+const HEADER: &str = "timestamp,timer,action,elapsed_ms,task";
+
+/// Something that happened to a timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    Increment,
+    Decrement,
+    Clear,
+    Lap,
+    ClearLaps,
+    Restart,
+    Skip,
+    Reset,
+    /// The elapsed time was typed in.
+    Set,
+    /// Switched to another task.
+    Task,
+    /// Went back to how the timer was before an earlier event.
+    Undo,
+    /// Took back an undo.
+    Redo,
+    /// Undid a clear, bringing back the time that was cleared.
+    Restore,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Stop => "stop",
+            Action::Increment => "increment",
+            Action::Decrement => "decrement",
+            Action::Clear => "clear",
+            Action::Lap => "lap",
+            Action::ClearLaps => "clear_laps",
+            Action::Restart => "restart",
+            Action::Skip => "skip",
+            Action::Reset => "reset",
+            Action::Set => "set",
+            Action::Task => "task",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Restore => "restore",
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "start" => Action::Start,
+            "stop" => Action::Stop,
+            "increment" => Action::Increment,
+            "decrement" => Action::Decrement,
+            "clear" => Action::Clear,
+            "lap" => Action::Lap,
+            "clear_laps" => Action::ClearLaps,
+            "restart" => Action::Restart,
+            "skip" => Action::Skip,
+            "reset" => Action::Reset,
+            "set" => Action::Set,
+            "task" => Action::Task,
+            "undo" => Action::Undo,
+            "redo" => Action::Redo,
+            "restore" => Action::Restore,
+            _ => return Err(format!("unknown action {s:?}")),
+        })
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `$XDG_STATE_HOME/osd-timer/history.csv`.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::state::dir()?.join("history.csv"))
+}
+
+/// One row of the history.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub at: DateTime<Local>,
+    pub timer: String,
+    pub action: Action,
+    pub elapsed: Duration,
+    /// The task the timer is on after the event, empty if it has none.
+    pub task: String,
+}
+
+/// Splits CSV text into records, handling quoted fields with commas, quotes
+/// and line breaks in them.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Reads every event in the history at `path`, oldest first. Rows that
+/// don't parse, such as one cut short by a crash, are skipped so the rest
+/// still counts; they come back as errors next to the events.
+pub fn read(path: &Path) -> Result<(Vec<Event>, Vec<String>), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let mut events = vec![];
+    let mut skipped = vec![];
+    for (index, record) in csv_records(&text).into_iter().enumerate() {
+        if index == 0 && record.first().is_some_and(|at| at == "timestamp") {
+            continue;
+        }
+        match parse_event(&record) {
+            Ok(event) => events.push(event),
+            Err(err) => skipped.push(format!("{}: row {}: {err}", path.display(), index + 1)),
+        }
+    }
+    Ok((events, skipped))
+}
+
+fn parse_event(record: &[String]) -> Result<Event, String> {
+    // Rows written before tasks existed have no task field.
+    let (at, timer, action, elapsed, task) = match record {
+        [at, timer, action, elapsed] => (at, timer, action, elapsed, ""),
+        [at, timer, action, elapsed, task] => (at, timer, action, elapsed, task.as_str()),
+        _ => return Err(format!("expected 5 fields, got {}", record.len())),
+    };
+    Ok(Event {
+        at: at
+            .parse::<DateTime<Local>>()
+            .map_err(|err| format!("{at:?}: {err}"))?,
+        timer: timer.clone(),
+        action: action.parse()?,
+        elapsed: Duration::from_millis(
+            elapsed
+                .parse()
+                .map_err(|err| format!("{elapsed:?}: {err}"))?,
+        ),
+        task: task.to_string(),
+    })
+}
+
+/// Quotes `field` if it would otherwise be misread as CSV.
+pub fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// An append-only CSV log of timer events, one row per event:
+/// `timestamp,timer,action,elapsed_ms,task`, where `elapsed_ms` is the timer's
+/// accumulated time right after the event and `task` what it is booked to.
+#[derive(Debug, Clone)]
+pub struct History {
+    pub path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Appends one event. The file is reopened every time, so each row is on
+    /// disk as soon as this returns, whatever happens to the process later.
+    pub fn record(
+        &self,
+        at: DateTime<Local>,
+        timer: &str,
+        action: Action,
+        elapsed: Duration,
+        task: &str,
+    ) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut row = String::new();
+        if file.metadata()?.len() == 0 {
+            row.push_str(HEADER);
+            row.push('\n');
+        }
+        row.push_str(&format!(
+            "{},{},{action},{},{}\n",
+            at.to_rfc3339_opts(SecondsFormat::Millis, false),
+            csv_field(timer),
+            elapsed.as_millis(),
+            csv_field(task),
+        ));
+        // A single write keeps rows whole even with several instances
+        // appending to the same file.
+        file.write_all(row.as_bytes())
+    }
+}
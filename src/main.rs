@@ -1,14 +1,42 @@
-use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 use chrono::Local;
 use macroquad::miniquad::conf::Platform;
 use macroquad::prelude::*;
+use macroquad::ui::{root_ui, widgets};
 
-use macroquad_grid::Grid;
+use macroquad_grid::{EditEvent, Grid};
 use static_init::dynamic;
 
+pub mod control;
+pub mod countdown;
+pub mod ctl;
+pub mod deadline;
+pub mod duration;
 pub mod fmt;
+pub mod history;
+pub mod pomodoro;
+pub mod report;
+pub mod schedule;
+pub mod sequence;
+pub mod signals;
+pub mod state;
+pub mod stopwatch;
+pub mod undo;
+
+use control::{Control, Request};
+use countdown::Countdown;
+use deadline::{Deadline, Expired};
+use fmt::DurationFormat;
+use history::{Action, History};
+use pomodoro::{Phase, Pomodoro, PomodoroConfig};
+use schedule::Schedule;
+use sequence::{Sequence, Steps};
+use signals::Signal;
+use state::State;
+use stopwatch::Stopwatch;
+use undo::UndoStack;
 
 #[macro_export]
 macro_rules! id {
@@ -61,7 +89,7 @@ macro_rules! impl_arg {
 }
 
 impl_arg! {
-    String, usize, bool
+    String, usize, bool, Expired, DurationFormat, report::Period, report::Past, report::GroupBy, report::ReportFormat
 }
 
 impl<T> Arg for Vec<T>
@@ -77,26 +105,107 @@ where
     }
 }
 
+impl<T> Arg for Option<T>
+where
+    T: Arg,
+    T: Default,
+{
+    fn from_string(&mut self, s: String) -> Result<(), String> {
+        let mut x = T::default();
+        x.from_string(s)?;
+        *self = Some(x);
+        Ok(())
+    }
+}
+
+/// A value with an optional `label=` prefix, e.g. `standup=09:30`. A `~`
+/// before the value shows it as relative phrasing ("in about 3 hours")
+/// instead of an exact duration, e.g. `standup=~09:30`. Labels can't contain
+/// `=`, so that they always read back the same.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Labeled<T> {
+    pub label: String,
+    pub relative: bool,
+    pub value: T,
+}
+
+impl<T> Labeled<T> {
+    pub fn map<U>(&self, f: impl FnOnce(&T) -> U) -> Labeled<U> {
+        Labeled {
+            label: self.label.clone(),
+            relative: self.relative,
+            value: f(&self.value),
+        }
+    }
+}
+
+/// Writes the value back in the form [`Arg::from_string`] accepts.
+impl<T: std::fmt::Display> std::fmt::Display for Labeled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tilde = if self.relative { "~" } else { "" };
+        write!(f, "{}={tilde}{}", self.label, self.value)
+    }
+}
+
+impl<T: Arg> Arg for Labeled<T> {
+    fn from_string(&mut self, s: String) -> Result<(), String> {
+        let (label, value) = match s.split_once('=') {
+            Some((label, value)) => (label.trim().to_string(), value),
+            None => (String::new(), s.as_str()),
+        };
+        if value.contains('=') {
+            return Err(format!("{s:?} has more than one =, labels can't contain ="));
+        }
+        let (relative, value) = match value.trim_start().strip_prefix('~') {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+        self.label = label;
+        self.relative = relative;
+        self.value.from_string(value.to_string())
+    }
+}
+
+impl Arg for Duration {
+    fn from_string(&mut self, s: String) -> Result<(), String> {
+        *self = duration::parse_duration(&s)?;
+        Ok(())
+    }
+}
+
+impl Arg for Steps {
+    /// A `@path` value reads the sequence from that file.
+    fn from_string(&mut self, s: String) -> Result<(), String> {
+        let s = match s.strip_prefix('@') {
+            Some(path) => std::fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?,
+            None => s,
+        };
+        *self = Steps::parse(&s)?;
+        Ok(())
+    }
+}
+
 impl Arg for chrono::DateTime<Local> {
     fn from_string(&mut self, s: String) -> Result<(), String> {
-        *self = s
-            .parse::<chrono::DateTime<Local>>()
-            .or_else(|_err| -> Result<_, String> {
-                let t = s
-                    .parse::<chrono::NaiveDateTime>()
-                    .map_err(|s| format!("{s}"))?;
-                let t = t.and_local_timezone(Local).unwrap();
-                Ok(t)
-            })
-            .or_else(|_err| -> Result<_, String> {
-                let t = s.parse::<chrono::NaiveTime>().map_err(|s| format!("{s}"))?;
-                let t = chrono::Local::now()
-                    .date_naive()
-                    .and_time(t)
-                    .and_local_timezone(Local)
-                    .unwrap();
-                Ok(t)
-            })?;
+        *self = deadline::parse_datetime(&s, Local::now())?;
+        Ok(())
+    }
+}
+
+impl Arg for Deadline {
+    fn from_string(&mut self, s: String) -> Result<(), String> {
+        if s.contains("..") {
+            let (start, end) = deadline::parse_window(&s, Local::now())?;
+            *self = Deadline::Between(start, end);
+            return Ok(());
+        }
+        let mut at = chrono::DateTime::<Local>::default();
+        *self = match at.from_string(s.clone()) {
+            Ok(()) => Deadline::At(at),
+            Err(err) => Deadline::Recurring(Schedule::parse(&s).map_err(|schedule_err| {
+                format!("{err}, or a recurring schedule ({schedule_err})")
+            })?),
+        };
         Ok(())
     }
 }
@@ -105,83 +214,756 @@ impl Arg for chrono::DateTime<Local> {
 #[field_iter(parse_iter_mut = "dyn Arg")]
 pub struct Options {
     pub time_format: String,
+    /// Used for stopwatches and laps, see [`DurationFormat`].
+    pub timer_format: DurationFormat,
+    /// Used for deadlines and everything that counts down.
+    pub deadline_format: DurationFormat,
+    /// Leave out seconds once a duration is at least a day long.
+    pub flag_adaptive_precision: bool,
     pub window_width: usize,
     pub window_height: usize,
-    pub deadline: Vec<chrono::DateTime<Local>>,
+    /// More can be added, changed and removed in the window with `d`.
+    pub deadline: Vec<Labeled<Deadline>>,
+    pub expired_deadlines: Expired,
+    pub deadline_flash: Duration,
+    pub stopwatch: Vec<String>,
+    /// The task the active stopwatch's time is booked to, `t` changes it.
+    pub task: String,
+    pub laps: usize,
+    /// How much `+`/`-` change a timer by, and with Shift, Ctrl or Alt held.
+    pub step: Duration,
+    pub shift_step: Duration,
+    pub ctrl_step: Duration,
+    pub alt_step: Duration,
+    /// How many commands `u` can take back.
+    pub undo_steps: usize,
+    pub countdown: Vec<Labeled<Duration>>,
+    pub flag_countdown_paused: bool,
+    pub flag_pomodoro: bool,
+    pub pomodoro_work: Duration,
+    pub pomodoro_short_break: Duration,
+    pub pomodoro_long_break: Duration,
+    pub pomodoro_long_break_every: usize,
+    /// Interval steps such as `warmup 5m, work 40s, rest 20s x8, cooldown 5m`,
+    /// see [`Steps::parse`]. A repeat after steps joined by commas covers the
+    /// last two of them; put the steps in parentheses to repeat others.
+    pub sequence: Steps,
+    pub sequence_repeat: usize,
+    pub sequence_lead_in: Duration,
+    /// Where stopwatches and added deadlines are kept across restarts.
+    /// Defaults to `$XDG_STATE_HOME/osd-timer/state`.
+    pub state_file: String,
+    pub flag_no_state: bool,
+    /// Where every start, stop and adjustment is logged as CSV. Defaults to
+    /// `$XDG_STATE_HOME/osd-timer/history.csv`.
+    pub history_file: String,
+    pub flag_no_history: bool,
+    /// Where scripts can control the running instance, see
+    /// [`control::Request`]. Defaults to `$XDG_RUNTIME_DIR/osd-timer.sock`.
+    pub control_socket: String,
+    pub flag_no_control: bool,
+    /// If an instance is already running, hand it this one's stopwatches,
+    /// countdowns, deadlines and task and exit instead of opening a window.
+    pub flag_single_instance: bool,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             time_format: "%T%.3f".to_string(),
+            timer_format: "%#-Hh %#-Mm %-S.%3fs".parse().unwrap(),
+            deadline_format: "%#-dd %#-Hh %#-Mm %-Ss".parse().unwrap(),
+            flag_adaptive_precision: false,
             window_width: 800,
             window_height: 200,
             deadline: vec![],
+            expired_deadlines: Expired::Keep,
+            deadline_flash: Duration::ZERO,
+            stopwatch: vec![],
+            task: String::new(),
+            laps: 0,
+            step: Duration::from_secs(1),
+            shift_step: Duration::from_secs(60),
+            ctrl_step: Duration::from_secs(10 * 60),
+            alt_step: Duration::from_secs(60 * 60),
+            undo_steps: 100,
+            countdown: vec![],
+            flag_countdown_paused: false,
+            flag_pomodoro: false,
+            pomodoro_work: Duration::from_secs(25 * 60),
+            pomodoro_short_break: Duration::from_secs(5 * 60),
+            pomodoro_long_break: Duration::from_secs(15 * 60),
+            pomodoro_long_break_every: 4,
+            sequence: Steps::default(),
+            sequence_repeat: 1,
+            sequence_lead_in: Duration::ZERO,
+            state_file: String::new(),
+            flag_no_state: false,
+            history_file: String::new(),
+            flag_no_history: false,
+            control_socket: String::new(),
+            flag_no_control: false,
+            flag_single_instance: false,
         }
     }
 }
 
-fn parse_args() -> Options {
-    let mut result = Options::default();
-    let mut it = std::env::args().skip(1);
+/// Fills in an options struct from `--name value` pairs, exiting on anything
+/// it doesn't know. `parse_iter_mut` is the struct's generated field visitor.
+pub fn parse_fields(
+    it: impl Iterator<Item = String>,
+    parse_iter_mut: impl FnMut(&mut dyn FnMut(&str, &mut dyn Arg) -> Option<()>) -> Option<()>,
+) {
+    if let Err(err) = try_parse_fields(it, parse_iter_mut) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+/// Like [`parse_fields`], but returns the first problem instead of exiting.
+pub fn try_parse_fields(
+    mut it: impl Iterator<Item = String>,
+    mut parse_iter_mut: impl FnMut(&mut dyn FnMut(&str, &mut dyn Arg) -> Option<()>) -> Option<()>,
+) -> Result<(), String> {
     while let Some(arg) = it.next() {
         let arg = arg.strip_prefix("--").unwrap_or(&arg);
-        let found = result
-            .parse_iter_mut(|name, opt| {
-                if arg == name {
-                    let param = if arg.starts_with("flag_") {
-                        "true".to_string()
-                    } else {
-                        it.next().unwrap_or_else(|| {
-                            eprintln!("Missing arg for {name}");
-                            std::process::exit(1)
-                        })
-                    };
-                    opt.from_string(param).unwrap_or_else(|err| {
-                        eprintln!("Failed to parse arg for {name}: {err}");
-                        std::process::exit(1)
-                    });
-                    return Some(());
-                }
-                None
-            })
-            .is_some();
+        let mut result = Ok(());
+        let found = parse_iter_mut(&mut |name, opt| {
+            if arg == name {
+                let param = if arg.starts_with("flag_") {
+                    Ok("true".to_string())
+                } else {
+                    it.next().ok_or_else(|| format!("Missing arg for {name}"))
+                };
+                result = param.and_then(|param| {
+                    opt.from_string(param)
+                        .map_err(|err| format!("Failed to parse arg for {name}: {err}"))
+                });
+                return Some(());
+            }
+            None
+        })
+        .is_some();
+        result?;
         if !found {
-            eprintln!("Unknown parameter {arg:?}");
-            std::process::exit(1);
+            return Err(format!("Unknown parameter {arg:?}"));
         }
     }
+    Ok(())
+}
+
+/// The options on the command line. Reading them again rereads the files
+/// they point at, such as `--sequence @path`.
+fn read_args() -> Result<Options, String> {
+    let mut result = Options::default();
+    try_parse_fields(std::env::args().skip(1), |f| result.parse_iter_mut(f))?;
+    result.timer_format.adaptive_precision = result.flag_adaptive_precision;
+    result.deadline_format.adaptive_precision = result.flag_adaptive_precision;
+    Ok(result)
+}
+
+fn parse_args() -> Options {
+    let result = read_args().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1)
+    });
     eprintln!("{result:#?}");
     result
 }
 
-#[dynamic]
+// Lazy, so that subcommands never parse the window's options.
+#[dynamic(lazy)]
 static OPTIONS: Options = parse_args();
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let mut grid = Grid::new(
-        screen_width(),
-        screen_height(),
-        2 + OPTIONS.deadline.len(),
-        1,
-        5.0,
+fn format_elapsed(elapsed: Duration) -> impl std::fmt::Display {
+    OPTIONS.timer_format.display(elapsed, false)
+}
+
+fn format_remaining(remaining: Duration) -> impl std::fmt::Display {
+    OPTIONS.deadline_format.display(remaining, false)
+}
+
+/// Shifts from green to red as `progress` goes from 0.0 to 1.0.
+fn progress_color(progress: f32) -> Color {
+    Color::new(
+        LIME.r + (RED.r - LIME.r) * progress,
+        LIME.g + (RED.g - LIME.g) * progress,
+        LIME.b + (RED.b - LIME.b) * progress,
+        1.0,
+    )
+}
+
+/// The time left until (or since) `next`, exact unless the deadline asked
+/// for relative phrasing.
+fn deadline_text(
+    deadline: &Labeled<Deadline>,
+    now: chrono::DateTime<Local>,
+    next: chrono::DateTime<Local>,
+) -> String {
+    if deadline.relative {
+        fmt::Relative(next.signed_duration_since(now).num_seconds()).to_string()
+    } else {
+        OPTIONS
+            .deadline_format
+            .display_between(now, next)
+            .to_string()
+    }
+}
+
+/// Reads a deadline as typed in: anything `--deadline` takes, or a plain
+/// duration, which counts from now like `in 5m`.
+fn parse_deadline(text: &str) -> Result<Deadline, String> {
+    let mut deadline = Deadline::default();
+    if let Err(err) = deadline.from_string(text.to_string()) {
+        let in_ = duration::parse_duration(text).map_err(|_| err)?;
+        let at = deadline::after(Local::now(), in_)
+            .ok_or_else(|| format!("{text:?} is too far ahead"))?;
+        deadline = Deadline::At(at);
+    }
+    Ok(deadline)
+}
+
+/// The label and deadline fields of the deadline editor, as a deadline.
+/// A leading `~` on the deadline asks for relative phrasing, as on the
+/// command line.
+fn parse_deadline_row(label: &str, text: &str) -> Result<Labeled<Deadline>, String> {
+    if label.contains('=') {
+        return Err(format!("{label:?}: labels can't contain ="));
+    }
+    let (relative, text) = match text.trim().strip_prefix('~') {
+        Some(text) => (true, text.trim()),
+        None => (false, text.trim()),
+    };
+    Ok(Labeled {
+        label: label.trim().to_string(),
+        relative,
+        value: parse_deadline(text)?,
+    })
+}
+
+fn state_path() -> Option<std::path::PathBuf> {
+    if OPTIONS.flag_no_state {
+        None
+    } else if OPTIONS.state_file.is_empty() {
+        state::default_path()
+    } else {
+        Some(OPTIONS.state_file.clone().into())
+    }
+}
+
+fn history() -> Option<History> {
+    if OPTIONS.flag_no_history {
+        None
+    } else if OPTIONS.history_file.is_empty() {
+        history::default_path().map(History::new)
+    } else {
+        Some(History::new(&OPTIONS.history_file))
+    }
+}
+
+fn control_path() -> Option<std::path::PathBuf> {
+    if OPTIONS.flag_no_control {
+        None
+    } else if OPTIONS.control_socket.is_empty() {
+        control::default_path()
+    } else {
+        Some(OPTIONS.control_socket.clone().into())
+    }
+}
+
+/// Picks up where a saved `state` left off: stopwatches take over the saved
+/// one of the same name, and the deadlines go back to the ones given on the
+/// command line, less the dropped ones, plus the saved ones.
+fn restore_state(
+    mut state: State,
+    stopwatches: &mut Vec<Stopwatch>,
+    active_stopwatch: &mut usize,
+    deadlines: &mut Vec<Labeled<Deadline>>,
+) {
+    let active_name = state
+        .stopwatches
+        .get(state.active_stopwatch)
+        .map(|saved| saved.name.clone());
+    for stopwatch in stopwatches.iter_mut() {
+        let saved = state
+            .stopwatches
+            .iter()
+            .position(|saved| saved.name == stopwatch.name);
+        if let Some(index) = saved {
+            *stopwatch = state.stopwatches.remove(index);
+        }
+    }
+    // Saved stopwatches that weren't asked for again, such as ones added
+    // while running, come back after the others.
+    stopwatches.append(&mut state.stopwatches);
+    let active = stopwatches
+        .iter()
+        .position(|stopwatch| Some(&stopwatch.name) == active_name.as_ref());
+    if let Some(active) = active {
+        *active_stopwatch = active;
+    }
+    // Each with whether it was dropped, which only counts while the label
+    // still matches, in case the command line changed since.
+    let mut restored: Vec<(Labeled<Deadline>, bool)> = OPTIONS
+        .deadline
+        .iter()
+        .enumerate()
+        .map(|(index, deadline)| {
+            let dropped = state
+                .dropped_deadlines
+                .contains(&(index, deadline.label.clone()));
+            (deadline.clone(), dropped)
+        })
+        .collect();
+    for saved in state.deadlines {
+        if OPTIONS.deadline.contains(&saved) {
+            continue;
+        }
+        // A labeled deadline that was moved takes the original's place.
+        let same_label = |(deadline, _): &&mut (Labeled<Deadline>, bool)| {
+            !saved.label.is_empty() && deadline.label == saved.label
+        };
+        let moved = restored
+            .iter_mut()
+            .filter(same_label)
+            .max_by_key(|(_, dropped)| *dropped);
+        match moved {
+            Some(slot) => *slot = (saved, false),
+            None => restored.push((saved, false)),
+        }
+    }
+    *deadlines = restored
+        .into_iter()
+        .filter(|(_, dropped)| !dropped)
+        .map(|(deadline, _)| deadline)
+        .collect();
+}
+
+/// Passes the timers and task given on the command line to an instance that
+/// is already running. Returns false if there is none to pass them to.
+fn forward_to_running() -> bool {
+    let Some(path) = control_path() else {
+        return false;
+    };
+    if ctl::send(&path, "status").is_err() {
+        return false;
+    }
+    let requests = OPTIONS
+        .stopwatch
+        .iter()
+        .map(|name| format!("add-stopwatch {name}"))
+        .chain(OPTIONS.countdown.iter().map(|countdown| {
+            let countdown = countdown.map(|duration| format!("{}ms", duration.as_millis()));
+            format!("add-countdown {countdown}")
+        }))
+        .chain(
+            OPTIONS
+                .deadline
+                .iter()
+                .map(|deadline| format!("add-deadline {deadline}")),
+        )
+        .chain((!OPTIONS.task.is_empty()).then(|| format!("task {}", OPTIONS.task)));
+    for request in requests {
+        if let Err(err) = ctl::send(&path, &request) {
+            eprintln!("{request}: {err}");
+        }
+    }
+    true
+}
+
+/// A countdown given on the command line, running unless asked not to be.
+fn start_countdown(countdown: &Labeled<Duration>) -> Labeled<Countdown> {
+    let mut countdown = countdown.map(|duration| Countdown::new(*duration));
+    if !OPTIONS.flag_countdown_paused {
+        countdown.value.toggle();
+    }
+    countdown
+}
+
+/// The stopwatch a control request is for: the one with that number or name,
+/// or the active one if it names none.
+fn find_stopwatch(
+    stopwatches: &[Stopwatch],
+    active_stopwatch: usize,
+    timer: Option<&str>,
+) -> Result<usize, String> {
+    let Some(timer) = timer else {
+        return Ok(active_stopwatch);
+    };
+    if let Ok(number @ 1..) = timer.parse::<usize>() {
+        if number <= stopwatches.len() {
+            return Ok(number - 1);
+        }
+    }
+    stopwatches
+        .iter()
+        .position(|stopwatch| stopwatch.name == timer)
+        .ok_or_else(|| format!("no stopwatch {timer:?}"))
+}
+
+/// The name a timer goes by in the history, `<kind> <n>` if it has none.
+fn timer_name(name: &str, kind: &str, index: usize) -> String {
+    if name.is_empty() {
+        format!("{kind} {}", index + 1)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Saves everything that should survive a restart. Deadlines from the
+/// command line are left out, they come back with the same arguments; the
+/// ones that were moved, edited or removed are noted as dropped instead.
+fn save_state(
+    path: &std::path::Path,
+    active_stopwatch: usize,
+    stopwatches: &[Stopwatch],
+    deadlines: &[Labeled<Deadline>],
+) {
+    let state = State {
+        active_stopwatch,
+        stopwatches: stopwatches.to_vec(),
+        deadlines: deadlines
+            .iter()
+            .filter(|deadline| !OPTIONS.deadline.contains(deadline))
+            .cloned()
+            .collect(),
+        dropped_deadlines: OPTIONS
+            .deadline
+            .iter()
+            .enumerate()
+            .filter(|(_, deadline)| !deadlines.contains(deadline))
+            .map(|(index, deadline)| (index, deadline.label.clone()))
+            .collect(),
+    };
+    if let Err(err) = state.save(path) {
+        eprintln!("Failed to save state to {}: {err}", path.display());
+    }
+}
+
+/// The step for `+`/`-`, picked by the largest modifier held.
+fn adjustment_step() -> Duration {
+    let down = |left, right| is_key_down(left) || is_key_down(right);
+    if down(KeyCode::LeftAlt, KeyCode::RightAlt) {
+        OPTIONS.alt_step
+    } else if down(KeyCode::LeftControl, KeyCode::RightControl) {
+        OPTIONS.ctrl_step
+    } else if down(KeyCode::LeftShift, KeyCode::RightShift) {
+        OPTIONS.shift_step
+    } else {
+        OPTIONS.step
+    }
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// Draws `message` on a dark box in the bottom right corner.
+fn draw_toast(message: &str) {
+    let font_size = (screen_height() * 0.15).max(16.0);
+    let size = measure_text(message, None, font_size as u16, 1.0);
+    let padding = font_size * 0.3;
+    let x = screen_width() - size.width - 2.0 * padding;
+    let y = screen_height() - size.height - 2.0 * padding;
+    draw_rectangle(
+        x,
+        y,
+        size.width + 2.0 * padding,
+        size.height + 2.0 * padding,
+        Color::new(0.0, 0.0, 0.0, 0.8),
+    );
+    draw_text(
+        message,
+        x + padding,
+        y + padding + size.offset_y,
+        font_size,
+        WHITE,
     );
-    let mut timer_total = Duration::from_secs(0);
-    let mut timer_start: Option<Instant> = None;
+}
+
+fn caption(label: &str) -> Option<&str> {
+    (!label.is_empty()).then_some(label)
+}
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("report") => {
+            if let Err(err) = report::run(std::env::args().skip(2)) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Some("ctl") => {
+            if let Err(err) = ctl::run(std::env::args().skip(2)) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            if OPTIONS.flag_single_instance && forward_to_running() {
+                return;
+            }
+            macroquad::Window::from_config(window_conf(), run())
+        }
+    }
+}
+
+async fn run() {
+    let mut stopwatches: Vec<Stopwatch> = if OPTIONS.stopwatch.is_empty() {
+        vec![Stopwatch::default()]
+    } else {
+        OPTIONS.stopwatch.iter().map(Stopwatch::new).collect()
+    };
+    let mut active_stopwatch = 0;
+    /// A cell that can be typed into.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        Task(usize),
+        Stopwatch(usize),
+        Countdown(usize),
+        Deadline(usize),
+    }
+    // The cell being typed into, if any.
+    let mut editing: Option<Field> = None;
+    // The last `laps` laps of the active stopwatch, then its best and worst lap.
+    let lap_cells = if OPTIONS.laps > 0 {
+        OPTIONS.laps + 2
+    } else {
+        0
+    };
+    let mut deadlines = OPTIONS.deadline.clone();
+    let state_path = state_path();
+    if let Some(path) = &state_path {
+        match State::load(path) {
+            Ok((state, skipped)) => {
+                for err in skipped {
+                    eprintln!("Skipped a saved record: {err}");
+                }
+                restore_state(
+                    state,
+                    &mut stopwatches,
+                    &mut active_stopwatch,
+                    &mut deadlines,
+                );
+            }
+            Err(err) => eprintln!("Failed to restore state: {err}"),
+        }
+    }
+    if let Err(err) = signals::install() {
+        eprintln!("Failed to catch signals: {err}");
+    }
+    let history = history();
+    let record = |timer: &str, action: Action, elapsed: Duration, task: &str| {
+        if let Some(history) = &history {
+            if let Err(err) = history.record(Local::now(), timer, action, elapsed, task) {
+                eprintln!("Failed to write {}: {err}", history.path.display());
+            }
+        }
+    };
+    if !OPTIONS.task.is_empty() {
+        let stopwatch = &mut stopwatches[active_stopwatch];
+        stopwatch.set_task(&OPTIONS.task);
+        let name = timer_name(&stopwatch.name, "stopwatch", active_stopwatch);
+        record(&name, Action::Task, stopwatch.elapsed(), &stopwatch.task);
+    }
+    let mut countdowns: Vec<Labeled<Countdown>> =
+        OPTIONS.countdown.iter().map(start_countdown).collect();
+    let mut pomodoro = OPTIONS.flag_pomodoro.then(|| {
+        Pomodoro::new(PomodoroConfig {
+            work: OPTIONS.pomodoro_work,
+            short_break: OPTIONS.pomodoro_short_break,
+            long_break: OPTIONS.pomodoro_long_break,
+            long_break_every: OPTIONS.pomodoro_long_break_every,
+        })
+    });
+    let mut sequence = (!OPTIONS.sequence.0.is_empty()).then(|| {
+        Sequence::new(
+            &OPTIONS.sequence,
+            OPTIONS.sequence_repeat,
+            OPTIONS.sequence_lead_in,
+        )
+    });
+    let mut grid = Grid::new(screen_width(), screen_height(), 1, 1, 5.0);
     // let min_size = grid.dimensions();
-    #[derive(Hash, Eq, PartialEq)]
+    #[derive(Eq, PartialEq)]
     enum Commands {
-        ToggleTimer,
-        IncrementTimer,
-        DecrementTimer,
-        PauseTimer,
-        StartTimer,
-        ClearTimer,
+        ToggleTimer(usize),
+        IncrementTimer(usize, Duration),
+        DecrementTimer(usize, Duration),
+        ExtendCountdown(usize, Duration),
+        ShortenCountdown(usize, Duration),
+        PostponeDeadline(usize, Duration),
+        AdvanceDeadline(usize, Duration),
+        PauseTimer(usize),
+        StartTimer(usize),
+        ClearTimer(usize),
+        LapTimer(usize),
+        ClearLaps(usize),
+        SetTask(usize, String),
+        SetTimer(usize, Duration),
+        SetCountdown(usize, Duration),
+        SetDeadline(usize, Deadline),
+        AddDeadline(Labeled<Deadline>),
+        /// Relabels and reschedules a deadline in one go.
+        EditDeadline(usize, Labeled<Deadline>),
+        RemoveDeadline(usize),
+        AddStopwatch(String),
+        AddCountdown(Labeled<Duration>),
+        /// Rereads the state file and the command line.
+        Reload,
+        Undo,
+        Redo,
+        SelectTimer(usize),
+        NextTimer,
+        PrevTimer,
+        ToggleCountdown(usize),
+        RestartCountdown(usize),
+        TogglePomodoro,
+        SkipPomodoroPhase,
+        ResetPomodoro,
+        ToggleSequence,
+        RestartSequence,
+        SkipSequenceStep,
         Quit,
     }
-    let mut commands = HashSet::new();
+    impl Commands {
+        /// The stopwatch the command is for, if it is for one.
+        fn stopwatch(&self) -> Option<usize> {
+            match self {
+                Commands::ToggleTimer(index)
+                | Commands::IncrementTimer(index, _)
+                | Commands::DecrementTimer(index, _)
+                | Commands::PauseTimer(index)
+                | Commands::StartTimer(index)
+                | Commands::ClearTimer(index)
+                | Commands::LapTimer(index)
+                | Commands::ClearLaps(index)
+                | Commands::SetTask(index, _)
+                | Commands::SetTimer(index, _) => Some(*index),
+                _ => None,
+            }
+        }
+    }
+    /// Turns what was typed into a cell into the command that applies it.
+    fn parse_field(field: Field, text: &str) -> Result<Commands, String> {
+        Ok(match field {
+            Field::Task(index) => Commands::SetTask(index, text.to_string()),
+            Field::Stopwatch(index) => Commands::SetTimer(index, duration::parse_duration(text)?),
+            Field::Countdown(index) => {
+                Commands::SetCountdown(index, duration::parse_duration(text)?)
+            }
+            Field::Deadline(index) => Commands::SetDeadline(index, parse_deadline(text)?),
+        })
+    }
+    /// Turns a control request into the command that carries it out, if it
+    /// takes one.
+    fn request_command(
+        request: Request,
+        stopwatches: &[Stopwatch],
+        active_stopwatch: usize,
+        countdowns: &[Labeled<Countdown>],
+        deadlines: &[Labeled<Deadline>],
+    ) -> Result<Option<Commands>, String> {
+        let find =
+            |timer: Option<String>| find_stopwatch(stopwatches, active_stopwatch, timer.as_deref());
+        Ok(Some(match request {
+            Request::Status => return Ok(None),
+            Request::Start(timer) => Commands::StartTimer(find(timer)?),
+            Request::Pause(timer) => Commands::PauseTimer(find(timer)?),
+            Request::Toggle(timer) => Commands::ToggleTimer(find(timer)?),
+            Request::Reset(timer) => Commands::ClearTimer(find(timer)?),
+            Request::Increment(timer, step) => Commands::IncrementTimer(find(timer)?, step),
+            Request::Decrement(timer, step) => Commands::DecrementTimer(find(timer)?, step),
+            Request::SetTask(task) => Commands::SetTask(active_stopwatch, task),
+            Request::AddStopwatch(name) => {
+                if stopwatches.iter().any(|stopwatch| stopwatch.name == name) {
+                    return Ok(None);
+                }
+                Commands::AddStopwatch(name)
+            }
+            Request::AddCountdown(countdown) => {
+                let exists = countdowns.iter().any(|existing| {
+                    existing.label == countdown.label && existing.value.duration == countdown.value
+                });
+                if exists {
+                    return Ok(None);
+                }
+                Commands::AddCountdown(countdown)
+            }
+            Request::AddDeadline(deadline) => {
+                if deadlines.contains(&deadline) {
+                    return Ok(None);
+                }
+                let same_label = deadlines.iter().position(|existing| {
+                    !deadline.label.is_empty() && existing.label == deadline.label
+                });
+                match same_label {
+                    Some(index) => Commands::EditDeadline(index, deadline),
+                    None => Commands::AddDeadline(deadline),
+                }
+            }
+            Request::Quit => Commands::Quit,
+        }))
+    }
+    let control = control_path().and_then(|path| match Control::listen(&path) {
+        Ok(control) => Some(control),
+        Err(err) => {
+            eprintln!("Failed to listen on {}: {err}", path.display());
+            None
+        }
+    });
+    // Control connections waiting for the state after this frame's commands.
+    let mut replies: Vec<control::Reply> = vec![];
+    // Run in the order they came in, one frame's worth at a time.
+    let mut commands = VecDeque::new();
+    let mut undo = UndoStack::new(OPTIONS.undo_steps);
+    // A short message in the corner, and when it appeared.
+    let mut toast: Option<(String, Instant)> = None;
+    // The deadline editor: whether it is open, the label and deadline typed
+    // into each of its rows, the deadlines those rows were filled from, the
+    // row for a new deadline, and the last error.
+    let mut deadline_window = false;
+    let mut deadline_rows: Vec<(String, String)> = vec![];
+    let mut deadline_rows_of: Vec<Labeled<Deadline>> = vec![];
+    let mut new_deadline = (String::new(), String::new());
+    let mut deadline_error: Option<String> = None;
     'outer: loop {
+        let now = Local::now();
+        if OPTIONS.expired_deadlines == Expired::Remove {
+            deadlines.retain(|deadline| !deadline.value.is_expired(now, OPTIONS.deadline_flash));
+        }
+        let task_col = 1 + stopwatches.len();
+        // The active stopwatch's task, while it has one or one is being typed.
+        let task_cells = usize::from(
+            matches!(editing, Some(Field::Task(_)))
+                || !stopwatches[active_stopwatch].task.is_empty(),
+        );
+        let lap_col = task_col + task_cells;
+        let deadline_col = lap_col + lap_cells;
+        let countdown_col = deadline_col + deadlines.len();
+        // The current phase, then the number of completed work phases.
+        let pomodoro_col = countdown_col + countdowns.len();
+        // The current step, the next step, then the overall remaining time.
+        let sequence_col = pomodoro_col + if pomodoro.is_some() { 2 } else { 0 };
+        let cols = sequence_col + if sequence.is_some() { 3 } else { 0 };
+        if cols != grid.cols() {
+            grid.resize(cols, None);
+            // Columns have shifted, so colors set for the old layout are stale.
+            grid.clear_colors();
+        }
+        let field_col = |field: Field| match field {
+            Field::Task(_) => Some(task_col),
+            Field::Stopwatch(index) => (index < stopwatches.len()).then_some(1 + index),
+            Field::Countdown(index) => (index < countdowns.len()).then_some(countdown_col + index),
+            Field::Deadline(index) => (index < deadlines.len()).then_some(deadline_col + index),
+        };
+        // Follow the edited cell around as columns come and go.
+        match editing.map(field_col) {
+            Some(Some(col)) => grid.move_editor(0, col),
+            Some(None) => {
+                editing = None;
+                grid.stop_editing();
+            }
+            None => (),
+        }
         clear_background(WHITE);
         set_default_camera();
         grid.set_cell_text(
@@ -190,79 +972,869 @@ async fn main() {
             Some(chrono::Local::now().format(&OPTIONS.time_format)),
         );
         (grid.width, grid.height) = (screen_width(), screen_height());
-        if grid.mouse_hovered_cell() == Some((0, 1)) {
-            if is_mouse_button_pressed(MouseButton::Left) {
-                commands.insert(Commands::ToggleTimer);
+        // Clicks on the deadline editor aren't meant for the cells under it.
+        let hovered_cell = grid.mouse_hovered_cell().filter(|_| !deadline_window);
+        if let Some((0, col)) = hovered_cell {
+            if (task_col..lap_col).contains(&col)
+                && is_mouse_button_pressed(MouseButton::Left)
+                && editing.is_none()
+            {
+                editing = Some(Field::Task(active_stopwatch));
+                grid.start_editing(0, col, &stopwatches[active_stopwatch].task);
+            }
+            if (1..task_col).contains(&col) {
+                let index = col - 1;
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    commands.push_back(Commands::SelectTimer(index));
+                    commands.push_back(Commands::ToggleTimer(index));
+                }
+                if is_mouse_button_pressed(MouseButton::Right) {
+                    commands.push_back(Commands::SelectTimer(index));
+                    commands.push_back(Commands::ClearTimer(index));
+                }
             }
-            if is_mouse_button_pressed(MouseButton::Right) {
-                commands.insert(Commands::ClearTimer);
+            if col == pomodoro_col && pomodoro.is_some() {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    commands.push_back(Commands::TogglePomodoro);
+                }
+                if is_mouse_button_pressed(MouseButton::Right) {
+                    commands.push_back(Commands::SkipPomodoroPhase);
+                }
+            }
+            if col == sequence_col && sequence.is_some() {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    commands.push_back(Commands::ToggleSequence);
+                }
+                if is_mouse_button_pressed(MouseButton::Right) {
+                    commands.push_back(Commands::RestartSequence);
+                }
+            }
+            if (countdown_col..pomodoro_col).contains(&col) {
+                let index = col - countdown_col;
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    commands.push_back(Commands::ToggleCountdown(index));
+                }
+                if is_mouse_button_pressed(MouseButton::Right) {
+                    commands.push_back(Commands::RestartCountdown(index));
+                }
             }
         }
-        match get_char_pressed() {
-            Some('=') | Some('+') => {
-                commands.insert(Commands::IncrementTimer);
+        if deadline_window {
+            // Refill the rows whenever the deadlines changed under them.
+            if deadline_rows_of != deadlines {
+                deadline_rows = deadlines
+                    .iter()
+                    .map(|deadline| {
+                        let tilde = if deadline.relative { "~" } else { "" };
+                        (deadline.label.clone(), format!("{tilde}{}", deadline.value))
+                    })
+                    .collect();
+                deadline_rows_of.clone_from(&deadlines);
             }
-            Some('-') => {
-                commands.insert(Commands::DecrementTimer);
+            let width = screen_width() - 20.0;
+            let field = |ratio: f32| vec2((width - 140.0) * ratio, 19.0);
+            deadline_window &=
+                widgets::Window::new(id!(), vec2(0.0, 0.0), vec2(screen_width(), screen_height()))
+                    .label("Deadlines")
+                    .close_button(true)
+                    .ui(&mut root_ui(), |ui| {
+                        for (index, (label, text)) in deadline_rows.iter_mut().enumerate() {
+                            widgets::InputText::new(id!(index))
+                                .size(field(0.3))
+                                .ui(ui, label);
+                            ui.same_line(0.0);
+                            widgets::InputText::new(id!(index))
+                                .size(field(0.7))
+                                .ui(ui, text);
+                            ui.same_line(0.0);
+                            if ui.button(None, "Apply") {
+                                match parse_deadline_row(label, text) {
+                                    Ok(deadline) => {
+                                        deadline_error = None;
+                                        commands.push_back(Commands::EditDeadline(index, deadline));
+                                    }
+                                    Err(err) => deadline_error = Some(err),
+                                }
+                            }
+                            ui.same_line(0.0);
+                            if ui.button(None, "Delete") {
+                                deadline_error = None;
+                                commands.push_back(Commands::RemoveDeadline(index));
+                            }
+                        }
+                        ui.separator();
+                        let (label, text) = &mut new_deadline;
+                        widgets::InputText::new(id!())
+                            .size(field(0.3))
+                            .ui(ui, label);
+                        ui.same_line(0.0);
+                        widgets::InputText::new(id!()).size(field(0.7)).ui(ui, text);
+                        ui.same_line(0.0);
+                        if ui.button(None, "Add") {
+                            match parse_deadline_row(label, text) {
+                                Ok(deadline) => {
+                                    deadline_error = None;
+                                    label.clear();
+                                    text.clear();
+                                    commands.push_back(Commands::AddDeadline(deadline));
+                                }
+                                Err(err) => deadline_error = Some(err),
+                            }
+                        }
+                        if let Some(err) = &deadline_error {
+                            widgets::Label::new(err).multiline(14.0).ui(ui);
+                        }
+                    });
+            // Typing goes to the editor, not the keymap.
+            while get_char_pressed().is_some() {}
+            if is_key_pressed(KeyCode::Escape) {
+                deadline_window = false;
             }
-            Some('q') => {
-                commands.insert(Commands::Quit);
+        } else if let Some(field) = editing {
+            match grid.update_editing() {
+                Some(EditEvent::Commit(text)) => {
+                    editing = None;
+                    match parse_field(field, text.trim()) {
+                        Ok(command) => {
+                            commands.push_back(command);
+                        }
+                        Err(err) => toast = Some((err, Instant::now())),
+                    }
+                }
+                Some(EditEvent::Cancel) => editing = None,
+                None => (),
             }
-            Some(' ') => {
-                commands.insert(Commands::PauseTimer);
+        } else if is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl) {
+            // Keep Ctrl+r from also clearing the timer below.
+            while get_char_pressed().is_some() {}
+            if is_key_pressed(KeyCode::Z) {
+                commands.push_back(Commands::Undo);
             }
-            Some('r') => {
-                commands.insert(Commands::ClearTimer);
+            if is_key_pressed(KeyCode::R) {
+                commands.push_back(Commands::Redo);
             }
-            Some('p') => {
-                commands.insert(Commands::StartTimer);
+        } else {
+            if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+                // Type into the countdown or deadline under the mouse, or else
+                // the active stopwatch.
+                let hovered = grid.mouse_hovered_cell().map(|(_, col)| col);
+                let field = match hovered {
+                    Some(col) if (deadline_col..countdown_col).contains(&col) => {
+                        Field::Deadline(col - deadline_col)
+                    }
+                    Some(col) if (countdown_col..pomodoro_col).contains(&col) => {
+                        Field::Countdown(col - countdown_col)
+                    }
+                    Some(col) if (1..task_col).contains(&col) => Field::Stopwatch(col - 1),
+                    _ => Field::Stopwatch(active_stopwatch),
+                };
+                if let Some(col) = field_col(field) {
+                    editing = Some(field);
+                    grid.start_editing(0, col, "");
+                }
+            }
+            if is_key_pressed(KeyCode::Tab) {
+                if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                    commands.push_back(Commands::PrevTimer);
+                } else {
+                    commands.push_back(Commands::NextTimer);
+                }
+            }
+            match get_char_pressed() {
+                Some('q') => {
+                    commands.push_back(Commands::Quit);
+                }
+                Some(' ') => {
+                    commands.push_back(Commands::PauseTimer(active_stopwatch));
+                }
+                Some('r') => {
+                    commands.push_back(Commands::ClearTimer(active_stopwatch));
+                }
+                Some('p') => {
+                    commands.push_back(Commands::StartTimer(active_stopwatch));
+                }
+                Some('l') => {
+                    commands.push_back(Commands::LapTimer(active_stopwatch));
+                }
+                Some('L') => {
+                    commands.push_back(Commands::ClearLaps(active_stopwatch));
+                }
+                Some('u') => {
+                    commands.push_back(Commands::Undo);
+                }
+                Some('d') => {
+                    deadline_window = true;
+                    deadline_error = None;
+                }
+                Some('t') => {
+                    editing = Some(Field::Task(active_stopwatch));
+                    grid.start_editing(0, task_col, &stopwatches[active_stopwatch].task);
+                }
+                Some(']') => {
+                    commands.push_back(Commands::NextTimer);
+                }
+                Some('[') => {
+                    commands.push_back(Commands::PrevTimer);
+                }
+                Some('c') => {
+                    commands.extend((0..countdowns.len()).map(Commands::ToggleCountdown));
+                }
+                Some('C') => {
+                    commands.extend((0..countdowns.len()).map(Commands::RestartCountdown));
+                }
+                Some('o') => {
+                    commands.push_back(Commands::TogglePomodoro);
+                }
+                Some('n') => {
+                    commands.push_back(Commands::SkipPomodoroPhase);
+                }
+                Some('O') => {
+                    commands.push_back(Commands::ResetPomodoro);
+                }
+                Some('i') => {
+                    commands.push_back(Commands::ToggleSequence);
+                }
+                Some('I') => {
+                    commands.push_back(Commands::RestartSequence);
+                }
+                Some('>') => {
+                    commands.push_back(Commands::SkipSequenceStep);
+                }
+                Some(c @ '1'..='9') => {
+                    commands.push_back(Commands::SelectTimer(c as usize - '1' as usize));
+                }
+                _ => (),
             }
-            _ => (),
         }
-        if timer_start.is_some() {
-            grid.color_cell(0, 1, GREEN);
-        } else {
-            grid.color_cell(0, 1, RED);
+        // `+`/`-` go by key rather than character, so that they still work
+        // with the modifiers that pick the step.
+        let up = is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd);
+        let down = is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract);
+        if editing.is_none() && !deadline_window && up != down {
+            let step = adjustment_step();
+            // The countdown or deadline under the mouse, or else the active stopwatch.
+            let hovered = grid.mouse_hovered_cell().map(|(_, col)| col);
+            commands.push_back(match hovered {
+                Some(col) if (deadline_col..countdown_col).contains(&col) => {
+                    let index = col - deadline_col;
+                    if up {
+                        Commands::PostponeDeadline(index, step)
+                    } else {
+                        Commands::AdvanceDeadline(index, step)
+                    }
+                }
+                Some(col) if (countdown_col..pomodoro_col).contains(&col) => {
+                    let index = col - countdown_col;
+                    if up {
+                        Commands::ExtendCountdown(index, step)
+                    } else {
+                        Commands::ShortenCountdown(index, step)
+                    }
+                }
+                _ if up => Commands::IncrementTimer(active_stopwatch, step),
+                _ => Commands::DecrementTimer(active_stopwatch, step),
+            });
+        }
+        while let Some((request, reply)) = control.as_ref().and_then(Control::next_request) {
+            let command = request_command(
+                request,
+                &stopwatches,
+                active_stopwatch,
+                &countdowns,
+                &deadlines,
+            );
+            match command {
+                Ok(command) => {
+                    commands.extend(command);
+                    replies.push(reply);
+                }
+                Err(err) => {
+                    let _ = reply.send(control::error(&err));
+                }
+            }
+        }
+        for signal in signals::take() {
+            commands.push_back(match signal {
+                Signal::Toggle => Commands::ToggleTimer(active_stopwatch),
+                Signal::Reset => Commands::ClearTimer(active_stopwatch),
+                Signal::Reload => Commands::Reload,
+                Signal::Quit => Commands::Quit,
+            });
         }
-        loop {
-            let Some(command) = commands.drain().next() else {
-                break;
+        let changed = !commands.is_empty();
+        while let Some(command) = commands.pop_front() {
+            // An undo earlier in the frame can take away the stopwatch a
+            // command was queued for.
+            if command
+                .stopwatch()
+                .is_some_and(|index| index >= stopwatches.len())
+            {
+                continue;
+            }
+            let stopwatch_name =
+                |index: usize| timer_name(&stopwatches[index].name, "stopwatch", index);
+            let label = match &command {
+                Commands::ToggleTimer(index) if stopwatches[*index].is_running() => {
+                    Some(format!("pause {}", stopwatch_name(*index)))
+                }
+                Commands::ToggleTimer(index) => Some(format!("start {}", stopwatch_name(*index))),
+                Commands::IncrementTimer(index, step) => Some(format!(
+                    "add {} to {}",
+                    format_elapsed(*step),
+                    stopwatch_name(*index)
+                )),
+                Commands::DecrementTimer(index, step) => Some(format!(
+                    "take {} off {}",
+                    format_elapsed(*step),
+                    stopwatch_name(*index)
+                )),
+                Commands::ExtendCountdown(index, step) => Some(format!(
+                    "add {} to countdown {}",
+                    format_remaining(*step),
+                    index + 1
+                )),
+                Commands::ShortenCountdown(index, step) => Some(format!(
+                    "take {} off countdown {}",
+                    format_remaining(*step),
+                    index + 1
+                )),
+                Commands::PostponeDeadline(index, step) => Some(format!(
+                    "postpone deadline {} by {}",
+                    index + 1,
+                    format_remaining(*step)
+                )),
+                Commands::AdvanceDeadline(index, step) => Some(format!(
+                    "bring deadline {} forward by {}",
+                    index + 1,
+                    format_remaining(*step)
+                )),
+                Commands::ClearTimer(index) => Some(format!("clear {}", stopwatch_name(*index))),
+                Commands::LapTimer(index) => Some(format!("lap on {}", stopwatch_name(*index))),
+                Commands::ClearLaps(index) => {
+                    Some(format!("clear laps of {}", stopwatch_name(*index)))
+                }
+                Commands::SetTask(index, task) => {
+                    Some(format!("task {task:?} on {}", stopwatch_name(*index)))
+                }
+                Commands::SetTimer(index, elapsed) => Some(format!(
+                    "set {} to {}",
+                    stopwatch_name(*index),
+                    format_elapsed(*elapsed)
+                )),
+                Commands::SetCountdown(index, remaining) => Some(format!(
+                    "set countdown {} to {}",
+                    index + 1,
+                    format_remaining(*remaining)
+                )),
+                Commands::SetDeadline(index, _) => Some(format!("move deadline {}", index + 1)),
+                Commands::AddDeadline(deadline) if deadline.label.is_empty() => {
+                    Some("add deadline".to_string())
+                }
+                Commands::AddDeadline(deadline) => {
+                    Some(format!("add deadline {:?}", deadline.label))
+                }
+                Commands::EditDeadline(index, _) => Some(format!("edit deadline {}", index + 1)),
+                Commands::RemoveDeadline(index) => Some(format!("remove deadline {}", index + 1)),
+                Commands::AddStopwatch(name) => Some(format!("add stopwatch {name:?}")),
+                Commands::Reload => Some("reload".to_string()),
+                Commands::AddCountdown(_) => {
+                    Some(format!("add countdown {}", countdowns.len() + 1))
+                }
+                Commands::ToggleCountdown(index) => Some(format!("toggle countdown {}", index + 1)),
+                Commands::RestartCountdown(index) => {
+                    Some(format!("restart countdown {}", index + 1))
+                }
+                Commands::TogglePomodoro => Some("toggle pomodoro".to_string()),
+                Commands::SkipPomodoroPhase => Some("skip pomodoro phase".to_string()),
+                Commands::ResetPomodoro => Some("reset pomodoro".to_string()),
+                Commands::ToggleSequence => Some("toggle sequence".to_string()),
+                Commands::RestartSequence => Some("restart sequence".to_string()),
+                Commands::SkipSequenceStep => Some("skip sequence step".to_string()),
+                // These only pass on to other commands or don't touch any timer.
+                Commands::StartTimer(_)
+                | Commands::PauseTimer(_)
+                | Commands::SelectTimer(_)
+                | Commands::NextTimer
+                | Commands::PrevTimer
+                | Commands::Undo
+                | Commands::Redo
+                | Commands::Quit => None,
             };
+            if let Some(label) = label {
+                // Undoing a clear gets logged differently, see `Action::Restore`.
+                let cleared = match command {
+                    Commands::ClearTimer(index) => Some(index),
+                    _ => None,
+                };
+                let snapshot = (
+                    stopwatches.clone(),
+                    countdowns.clone(),
+                    pomodoro.clone(),
+                    sequence.clone(),
+                    deadlines.clone(),
+                );
+                undo.push((label, cleared), snapshot);
+            }
             match command {
-                Commands::ToggleTimer => {
-                    timer_start = match timer_start {
-                        Some(start) => {
-                            timer_total += start.elapsed();
+                Commands::ToggleTimer(index) => {
+                    let stopwatch = &mut stopwatches[index];
+                    stopwatch.toggle();
+                    let action = if stopwatch.is_running() {
+                        Action::Start
+                    } else {
+                        Action::Stop
+                    };
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(&name, action, stopwatch.elapsed(), &stopwatch.task);
+                }
+                Commands::IncrementTimer(index, step) => {
+                    let stopwatch = &mut stopwatches[index];
+                    stopwatch.increment(step);
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(
+                        &name,
+                        Action::Increment,
+                        stopwatch.elapsed(),
+                        &stopwatch.task,
+                    );
+                }
+                Commands::DecrementTimer(index, step) => {
+                    let stopwatch = &mut stopwatches[index];
+                    stopwatch.decrement(step);
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(
+                        &name,
+                        Action::Decrement,
+                        stopwatch.elapsed(),
+                        &stopwatch.task,
+                    );
+                }
+                Commands::SetTimer(index, elapsed) => {
+                    let stopwatch = &mut stopwatches[index];
+                    stopwatch.set_elapsed(elapsed);
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(&name, Action::Set, stopwatch.elapsed(), &stopwatch.task);
+                }
+                Commands::SetCountdown(index, remaining) => {
+                    if let Some(countdown) = countdowns.get_mut(index) {
+                        countdown.value.set_remaining(remaining);
+                    }
+                }
+                Commands::SetDeadline(index, deadline) => {
+                    if let Some(labeled) = deadlines.get_mut(index) {
+                        labeled.value = deadline;
+                    }
+                }
+                Commands::AddDeadline(deadline) => deadlines.push(deadline),
+                Commands::EditDeadline(index, deadline) => {
+                    if let Some(labeled) = deadlines.get_mut(index) {
+                        *labeled = deadline;
+                    }
+                }
+                Commands::RemoveDeadline(index) => {
+                    if index < deadlines.len() {
+                        deadlines.remove(index);
+                    }
+                }
+                Commands::AddStopwatch(name) => stopwatches.push(Stopwatch::new(name)),
+                Commands::Reload => {
+                    let mut messages = vec![];
+                    match state_path.as_deref().map(State::load) {
+                        Some(Ok((state, skipped))) => {
+                            for err in &skipped {
+                                eprintln!("Skipped a saved record: {err}");
+                            }
+                            messages.push(match skipped.len() {
+                                0 => "reloaded state".to_string(),
+                                1 => "reloaded state, skipped a bad record".to_string(),
+                                n => format!("reloaded state, skipped {n} bad records"),
+                            });
+                            let before = stopwatches.clone();
+                            restore_state(
+                                state,
+                                &mut stopwatches,
+                                &mut active_stopwatch,
+                                &mut deadlines,
+                            );
+                            // Stopwatches edited in the file count as set by hand.
+                            for (index, (before, stopwatch)) in
+                                before.iter().zip(&stopwatches).enumerate()
+                            {
+                                let elapsed = stopwatch.elapsed();
+                                if before.elapsed().abs_diff(elapsed) >= Duration::from_secs(1) {
+                                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                                    record(&name, Action::Set, elapsed, &stopwatch.task);
+                                }
+                            }
+                        }
+                        Some(Err(err)) => messages.push(err),
+                        None => (),
+                    }
+                    // The command line is the same as at startup, so only what
+                    // it points at can have changed: a sequence read from a
+                    // file that changed starts over. Deadlines keep running as
+                    // they are, rather than being worked out again from now.
+                    let reread = match read_args() {
+                        Ok(options) => Some(options),
+                        Err(err) => {
+                            messages.push(err);
                             None
                         }
-                        None => Some(Instant::now()),
                     };
+                    let reread =
+                        reread
+                            .filter(|options| !options.sequence.0.is_empty())
+                            .map(|options| {
+                                Sequence::new(
+                                    &options.sequence,
+                                    options.sequence_repeat,
+                                    options.sequence_lead_in,
+                                )
+                            });
+                    if let (Some(sequence), Some(reread)) = (&mut sequence, reread) {
+                        if sequence.steps != reread.steps {
+                            if sequence.is_running() {
+                                record(
+                                    "sequence",
+                                    Action::Stop,
+                                    sequence.countdown.stopwatch.elapsed(),
+                                    "",
+                                );
+                            }
+                            *sequence = reread;
+                            messages.push("new sequence".to_string());
+                        }
+                    }
+                    if messages.is_empty() {
+                        messages.push("nothing to reload".to_string());
+                    }
+                    toast = Some((messages.join(", "), Instant::now()));
                 }
-                Commands::IncrementTimer => {
-                    timer_total += Duration::from_secs(1);
+                Commands::AddCountdown(countdown) => countdowns.push(start_countdown(&countdown)),
+                Commands::ExtendCountdown(index, step) => {
+                    if let Some(countdown) = countdowns.get_mut(index) {
+                        countdown.value.extend(step);
+                    }
                 }
-                Commands::DecrementTimer => {
-                    timer_total -= timer_total.min(Duration::from_secs(1));
+                Commands::ShortenCountdown(index, step) => {
+                    if let Some(countdown) = countdowns.get_mut(index) {
+                        countdown.value.shorten(step);
+                    }
                 }
-                Commands::StartTimer => {
-                    if timer_start.is_none() {
-                        commands.insert(Commands::ToggleTimer);
+                Commands::PostponeDeadline(index, step)
+                | Commands::AdvanceDeadline(index, step) => {
+                    let mut by = chrono::Duration::from_std(step)
+                        .unwrap_or_else(|_| chrono::Duration::zero());
+                    if matches!(command, Commands::AdvanceDeadline(..)) {
+                        by = -by;
+                    }
+                    if let Some(deadline) = deadlines.get_mut(index) {
+                        if let Err(err) = deadline.value.shift(by) {
+                            toast = Some((err.to_string(), Instant::now()));
+                        }
                     }
                 }
-                Commands::PauseTimer => {
-                    if timer_start.is_some() {
-                        commands.insert(Commands::ToggleTimer);
+                Commands::StartTimer(index) => {
+                    if !stopwatches[index].is_running() {
+                        commands.push_front(Commands::ToggleTimer(index));
                     }
                 }
-                Commands::ClearTimer => {
-                    timer_total = Duration::from_secs(0);
-                    timer_start = None;
+                Commands::PauseTimer(index) => {
+                    if stopwatches[index].is_running() {
+                        commands.push_front(Commands::ToggleTimer(index));
+                    }
+                }
+                Commands::ClearTimer(index) => {
+                    let stopwatch = &mut stopwatches[index];
+                    // The time it had is what gets billed, so log it first.
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(&name, Action::Clear, stopwatch.elapsed(), &stopwatch.task);
+                    stopwatch.clear();
+                }
+                Commands::LapTimer(index) => {
+                    let stopwatch = &mut stopwatches[index];
+                    if stopwatch.is_running() {
+                        stopwatch.lap();
+                        let name = timer_name(&stopwatch.name, "stopwatch", index);
+                        record(&name, Action::Lap, stopwatch.elapsed(), &stopwatch.task);
+                    }
+                }
+                Commands::SetTask(index, task) => {
+                    let stopwatch = &mut stopwatches[index];
+                    if stopwatch.task != task {
+                        stopwatch.set_task(task);
+                        let name = timer_name(&stopwatch.name, "stopwatch", index);
+                        record(&name, Action::Task, stopwatch.elapsed(), &stopwatch.task);
+                    }
+                }
+                Commands::ClearLaps(index) => {
+                    let stopwatch = &mut stopwatches[index];
+                    stopwatch.splits.clear();
+                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                    record(
+                        &name,
+                        Action::ClearLaps,
+                        stopwatch.elapsed(),
+                        &stopwatch.task,
+                    );
+                }
+                Commands::SelectTimer(index) => {
+                    if index < stopwatches.len() {
+                        active_stopwatch = index;
+                    }
+                }
+                Commands::NextTimer => {
+                    active_stopwatch = (active_stopwatch + 1) % stopwatches.len();
+                }
+                Commands::PrevTimer => {
+                    active_stopwatch =
+                        (active_stopwatch + stopwatches.len() - 1) % stopwatches.len();
+                }
+                Commands::ToggleCountdown(index) => {
+                    if let Some(countdown) = countdowns.get_mut(index) {
+                        countdown.value.toggle();
+                        let action = if countdown.value.is_running() {
+                            Action::Start
+                        } else {
+                            Action::Stop
+                        };
+                        let name = timer_name(&countdown.label, "countdown", index);
+                        record(&name, action, countdown.value.stopwatch.elapsed(), "");
+                    }
+                }
+                Commands::RestartCountdown(index) => {
+                    if let Some(countdown) = countdowns.get_mut(index) {
+                        let name = timer_name(&countdown.label, "countdown", index);
+                        record(
+                            &name,
+                            Action::Restart,
+                            countdown.value.stopwatch.elapsed(),
+                            "",
+                        );
+                        countdown.value.restart();
+                    }
+                }
+                Commands::TogglePomodoro => {
+                    if let Some(pomodoro) = &mut pomodoro {
+                        pomodoro.toggle();
+                        let action = if pomodoro.countdown.is_running() {
+                            Action::Start
+                        } else {
+                            Action::Stop
+                        };
+                        record(
+                            "pomodoro",
+                            action,
+                            pomodoro.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                    }
+                }
+                Commands::SkipPomodoroPhase => {
+                    if let Some(pomodoro) = &mut pomodoro {
+                        record(
+                            "pomodoro",
+                            Action::Skip,
+                            pomodoro.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                        pomodoro.skip();
+                    }
+                }
+                Commands::ResetPomodoro => {
+                    if let Some(pomodoro) = &mut pomodoro {
+                        record(
+                            "pomodoro",
+                            Action::Reset,
+                            pomodoro.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                        pomodoro.reset();
+                    }
+                }
+                Commands::ToggleSequence => {
+                    if let Some(sequence) = &mut sequence {
+                        sequence.toggle();
+                        let action = if sequence.is_running() {
+                            Action::Start
+                        } else {
+                            Action::Stop
+                        };
+                        record(
+                            "sequence",
+                            action,
+                            sequence.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                    }
+                }
+                Commands::RestartSequence => {
+                    if let Some(sequence) = &mut sequence {
+                        record(
+                            "sequence",
+                            Action::Restart,
+                            sequence.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                        sequence.restart();
+                    }
+                }
+                Commands::SkipSequenceStep => {
+                    if let Some(sequence) = &mut sequence {
+                        sequence.skip();
+                        record(
+                            "sequence",
+                            Action::Skip,
+                            sequence.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                    }
+                }
+                Commands::Undo | Commands::Redo => {
+                    let before = stopwatches.clone();
+                    let mut timers = (
+                        std::mem::take(&mut stopwatches),
+                        std::mem::take(&mut countdowns),
+                        pomodoro.take(),
+                        sequence.take(),
+                        std::mem::take(&mut deadlines),
+                    );
+                    let (label, action, done) = if command == Commands::Undo {
+                        (undo.undo(&mut timers), Action::Undo, "undid")
+                    } else {
+                        (undo.redo(&mut timers), Action::Redo, "redid")
+                    };
+                    (stopwatches, countdowns, pomodoro, sequence, deadlines) = timers;
+                    // Stopwatches added since may be gone again.
+                    active_stopwatch = active_stopwatch.min(stopwatches.len() - 1);
+                    let cleared = label.as_ref().and_then(|(_, cleared)| *cleared);
+                    let message = match label {
+                        Some((label, _)) => format!("{done} {label}"),
+                        None if action == Action::Undo => "nothing to undo".to_string(),
+                        None => "nothing to redo".to_string(),
+                    };
+                    toast = Some((message, Instant::now()));
+                    // Keep the history in line with what the stopwatches now show.
+                    for (index, (before, stopwatch)) in before.iter().zip(&stopwatches).enumerate()
+                    {
+                        let name = timer_name(&stopwatch.name, "stopwatch", index);
+                        if cleared == Some(index) && action == Action::Redo {
+                            // Cleared again, logged like the clear was.
+                            record(&name, Action::Clear, before.elapsed(), &before.task);
+                        } else if cleared == Some(index) {
+                            record(&name, Action::Restore, stopwatch.elapsed(), &stopwatch.task);
+                        } else if before.elapsed().abs_diff(stopwatch.elapsed())
+                            >= Duration::from_secs(1)
+                        {
+                            record(&name, action, stopwatch.elapsed(), &stopwatch.task);
+                        }
+                        if before.task != stopwatch.task {
+                            record(&name, Action::Task, stopwatch.elapsed(), &stopwatch.task);
+                        }
+                        if before.is_running() != stopwatch.is_running() {
+                            let action = if stopwatch.is_running() {
+                                Action::Start
+                            } else {
+                                Action::Stop
+                            };
+                            record(&name, action, stopwatch.elapsed(), &stopwatch.task);
+                        }
+                    }
                 }
                 Commands::Quit => {
+                    // Whatever isn't saved stops running here, as far as the
+                    // history is concerned.
+                    match &state_path {
+                        Some(path) => save_state(path, active_stopwatch, &stopwatches, &deadlines),
+                        None => {
+                            for (index, stopwatch) in stopwatches.iter().enumerate() {
+                                if stopwatch.is_running() {
+                                    let name = timer_name(&stopwatch.name, "stopwatch", index);
+                                    record(
+                                        &name,
+                                        Action::Stop,
+                                        stopwatch.elapsed(),
+                                        &stopwatch.task,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    for (index, countdown) in countdowns.iter().enumerate() {
+                        if countdown.value.is_running() {
+                            let name = timer_name(&countdown.label, "countdown", index);
+                            record(&name, Action::Stop, countdown.value.stopwatch.elapsed(), "");
+                        }
+                    }
+                    if let Some(pomodoro) = pomodoro.as_ref().filter(|p| p.is_running()) {
+                        record(
+                            "pomodoro",
+                            Action::Stop,
+                            pomodoro.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                    }
+                    if let Some(sequence) = sequence.as_ref().filter(|s| s.is_running()) {
+                        record(
+                            "sequence",
+                            Action::Stop,
+                            sequence.countdown.stopwatch.elapsed(),
+                            "",
+                        );
+                    }
+                    let status = control::status(
+                        active_stopwatch,
+                        &stopwatches,
+                        &countdowns,
+                        &deadlines,
+                        now,
+                    );
+                    for reply in replies.drain(..) {
+                        let _ = reply.send(status.clone());
+                    }
                     break 'outer;
                 }
             }
         }
+        // Timers that ran out stop here, so the history shows when they did.
+        for (index, countdown) in countdowns.iter_mut().enumerate() {
+            if countdown.value.finish() {
+                let name = timer_name(&countdown.label, "countdown", index);
+                record(&name, Action::Stop, countdown.value.stopwatch.elapsed(), "");
+            }
+        }
+        if let Some(sequence) = &mut sequence {
+            if sequence.countdown.finish() {
+                record(
+                    "sequence",
+                    Action::Stop,
+                    sequence.countdown.stopwatch.elapsed(),
+                    "",
+                );
+            }
+        }
+        if let Some(pomodoro) = &mut pomodoro {
+            let ended = pomodoro.countdown.stopwatch.elapsed();
+            if pomodoro.update() {
+                record("pomodoro", Action::Stop, ended, "");
+                record(
+                    "pomodoro",
+                    Action::Start,
+                    pomodoro.countdown.stopwatch.elapsed(),
+                    "",
+                );
+            }
+        }
+        if let Some(path) = state_path.as_ref().filter(|_| changed) {
+            save_state(path, active_stopwatch, &stopwatches, &deadlines);
+        }
+        if !replies.is_empty() {
+            let status =
+                control::status(active_stopwatch, &stopwatches, &countdowns, &deadlines, now);
+            for reply in replies.drain(..) {
+                let _ = reply.send(status.clone());
+            }
+        }
         // let new_size = if is_key_pressed(KeyCode::KpAdd) {
         //     let mut dim = grid.dimensions();
         //     dim.cols += 1;
@@ -272,46 +1844,169 @@ async fn main() {
         //     dim.cols += 1;
         //     dim
         // }.max(min_size);
-        let current_elapsed = timer_total + timer_start.map(|ts| ts.elapsed()).unwrap_or_default();
-        {
-            let content = fmt::FmtFn(|f| {
-                let mut secs = current_elapsed.as_secs();
-                let hours = secs / 3600;
-                secs -= hours * 3600;
-                let mins = secs / 60;
-                secs -= mins * 60;
-                if hours > 0 {
-                    write!(f, " {hours}h")?;
-                }
-                if mins > 0 {
-                    write!(f, " {mins}m")?;
-                }
-                write!(f, " {secs}.{:03}s", current_elapsed.as_millis() % 1000)
+        for (index, stopwatch) in stopwatches.iter().enumerate() {
+            let col = 1 + index;
+            if stopwatch.is_running() {
+                grid.color_cell(0, col, GREEN);
+            } else {
+                grid.color_cell(0, col, RED);
+            }
+            let elapsed = format_elapsed(stopwatch.elapsed());
+            let elapsed = if stopwatch.splits.is_empty() {
+                elapsed.to_string()
+            } else {
+                format!("{elapsed} | {}", format_elapsed(stopwatch.current_lap()))
+            };
+            grid.set_cell_text(0, col, Some(elapsed));
+            grid.set_cell_caption(0, col, caption(&stopwatch.name));
+        }
+        if task_cells > 0 {
+            let active = &stopwatches[active_stopwatch];
+            grid.clear_cell_color(0, task_col);
+            if matches!(editing, Some(Field::Task(_))) {
+                grid.set_cell_caption(0, task_col, Some("task"));
+            } else {
+                grid.set_cell_caption(0, task_col, caption(&active.task));
+            }
+            grid.set_cell_text(
+                0,
+                task_col,
+                Some(format_elapsed(active.task_elapsed(&active.task))),
+            );
+        }
+        if lap_cells > 0 {
+            let active = &stopwatches[active_stopwatch];
+            let mut recent = active.laps().enumerate().rev();
+            for col in lap_col..lap_col + OPTIONS.laps {
+                let lap = recent.next();
+                grid.set_cell_caption(0, col, lap.map(|(index, _)| format!("lap {}", index + 1)));
+                grid.set_cell_text(0, col, lap.map(|(_, lap)| format_elapsed(lap)));
+            }
+            grid.set_cell_caption(0, deadline_col - 2, Some("best"));
+            grid.set_cell_text(0, deadline_col - 2, active.laps().min().map(format_elapsed));
+            grid.set_cell_caption(0, deadline_col - 1, Some("worst"));
+            grid.set_cell_text(0, deadline_col - 1, active.laps().max().map(format_elapsed));
+        }
+        // Only highlight the active stopwatch when there is a choice to make.
+        if stopwatches.len() > 1 {
+            let active = &stopwatches[active_stopwatch];
+            grid.set_selected_cell_color(if active.is_running() {
+                DARKGREEN
+            } else {
+                MAROON
             });
-            grid.set_cell_text(0, 1, Some(content));
-        }
-        for (deadline_index, deadline) in OPTIONS.deadline.iter().enumerate() {
-            let duration = deadline
-                .signed_duration_since(Local::now())
-                .to_std()
-                .unwrap_or_default();
-            let content = fmt::FmtFn(|f| {
-                let mut secs = duration.as_secs();
-                let hours = secs / 3600;
-                secs -= hours * 3600;
-                let mins = secs / 60;
-                secs -= mins * 60;
-                if hours > 0 {
-                    write!(f, " {hours}h")?;
-                }
-                if mins > 0 {
-                    write!(f, " {mins}m")?;
-                }
-                write!(f, " {secs}s")
+            grid.select_cell(Some((0, 1 + active_stopwatch)));
+        }
+        for (deadline_index, deadline) in deadlines.iter().enumerate() {
+            let col = deadline_col + deadline_index;
+            grid.set_cell_caption(0, col, caption(&deadline.label));
+            let Some(next) = deadline.value.next(now) else {
+                grid.fill_cell(0, col, None);
+                grid.set_cell_text(0, col, None::<String>);
+                continue;
+            };
+            let remaining = next.signed_duration_since(now);
+            if remaining >= chrono::Duration::zero() {
+                let progress = deadline.value.progress(now);
+                grid.fill_cell(0, col, progress.map(|p| (p, progress_color(p))));
+                grid.clear_cell_color(0, col);
+                grid.set_cell_text(0, col, Some(deadline_text(deadline, now, next)));
+                continue;
+            }
+            grid.fill_cell(0, col, None);
+            let overdue = (-remaining).to_std().unwrap_or_default();
+            let flashing = overdue < OPTIONS.deadline_flash;
+            if flashing {
+                let phase = overdue.as_millis() / 250 % 2;
+                grid.color_cell(0, col, if phase == 0 { RED } else { YELLOW });
+            } else if OPTIONS.expired_deadlines == Expired::Hide {
+                grid.clear_cell_color(0, col);
+                grid.set_cell_text(0, col, None::<String>);
+                continue;
+            } else {
+                grid.color_cell(0, col, PINK);
+            }
+            grid.set_cell_text(0, col, Some(deadline_text(deadline, now, next)));
+        }
+        for (index, countdown) in countdowns.iter().enumerate() {
+            let col = countdown_col + index;
+            let Labeled {
+                label,
+                relative,
+                value: countdown,
+            } = countdown;
+            if countdown.is_finished() {
+                grid.color_cell(0, col, ORANGE);
+            } else if countdown.is_running() {
+                grid.color_cell(0, col, GREEN);
+            } else {
+                grid.color_cell(0, col, RED);
+            }
+            if *relative {
+                let remaining = countdown.remaining().as_secs() as i64;
+                grid.set_cell_text(0, col, Some(fmt::Relative(remaining)));
+            } else {
+                grid.set_cell_text(0, col, Some(format_remaining(countdown.remaining())));
+            }
+            grid.set_cell_caption(0, col, caption(label));
+        }
+        if let Some(pomodoro) = &pomodoro {
+            let color = match pomodoro.phase {
+                _ if !pomodoro.is_running() => LIGHTGRAY,
+                Phase::Work => ORANGE,
+                Phase::ShortBreak => SKYBLUE,
+                Phase::LongBreak => VIOLET,
+            };
+            grid.color_cell(0, pomodoro_col, color);
+            grid.set_cell_caption(0, pomodoro_col, Some(pomodoro.phase));
+            grid.set_cell_text(
+                0,
+                pomodoro_col,
+                Some(format_remaining(pomodoro.countdown.remaining())),
+            );
+            grid.set_cell_caption(0, pomodoro_col + 1, Some("done"));
+            grid.set_cell_text(0, pomodoro_col + 1, Some(pomodoro.completed));
+        }
+        if let Some(sequence) = &sequence {
+            let position = sequence.position();
+            let color = match position {
+                None => ORANGE,
+                Some(_) if !sequence.is_running() => RED,
+                Some(position) if position.lead_in.is_some() => YELLOW,
+                Some(_) => GREEN,
+            };
+            grid.color_cell(0, sequence_col, color);
+            let current = position.map(|position| match position.lead_in {
+                Some(lead_in) => format!("in {}", lead_in.as_secs() + 1),
+                None => format_remaining(position.remaining).to_string(),
             });
-            grid.set_cell_text(0, 2 + deadline_index, Some(content));
+            grid.set_cell_caption(
+                0,
+                sequence_col,
+                position.map(|position| &position.step.name),
+            );
+            grid.set_cell_text(0, sequence_col, Some(current.unwrap_or("done".to_string())));
+            grid.set_cell_caption(0, sequence_col + 1, Some("next"));
+            grid.set_cell_text(
+                0,
+                sequence_col + 1,
+                sequence.next_step().map(|step| &step.name),
+            );
+            grid.set_cell_caption(0, sequence_col + 2, Some("left"));
+            grid.set_cell_text(
+                0,
+                sequence_col + 2,
+                Some(format_remaining(sequence.remaining())),
+            );
         }
         grid.draw();
+        if let Some((message, shown_at)) = &toast {
+            if shown_at.elapsed() < TOAST_DURATION {
+                draw_toast(message);
+            } else {
+                toast = None;
+            }
+        }
         // draw_text_ex(
         //     &s,
         //     10.0,
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::countdown::Countdown;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Work => "work",
+            Phase::ShortBreak => "break",
+            Phase::LongBreak => "long break",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PomodoroConfig {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    /// Every `long_break_every`th break is a long one.
+    pub long_break_every: usize,
+}
+
+impl PomodoroConfig {
+    pub fn duration(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// Cycles work and break phases, each one timed by a [`Countdown`].
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    pub config: PomodoroConfig,
+    pub phase: Phase,
+    /// Number of finished work phases.
+    pub completed: usize,
+    pub countdown: Countdown,
+}
+
+impl Pomodoro {
+    pub fn new(config: PomodoroConfig) -> Self {
+        Self {
+            config,
+            phase: Phase::Work,
+            completed: 0,
+            countdown: Countdown::new(config.work),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.countdown.is_running()
+    }
+
+    pub fn toggle(&mut self) {
+        self.countdown.toggle();
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// Ends the current phase now and starts the next one.
+    pub fn skip(&mut self) {
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.completed += 1;
+                if self
+                    .completed
+                    .is_multiple_of(self.config.long_break_every.max(1))
+                {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        self.countdown = Countdown::new(self.config.duration(self.phase));
+        self.countdown.toggle();
+    }
+
+    /// Advances to the next phase once the current one has run out. Returns
+    /// whether the phase changed.
+    pub fn update(&mut self) -> bool {
+        if self.countdown.is_finished() {
+            self.skip();
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime};
+
+use crate::deadline;
+use crate::history::{self, Action, Event};
+use crate::Arg;
+
+/// The range a report covers when no `--since` is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Period {
+    /// Since midnight.
+    #[default]
+    Day,
+    /// Since Monday.
+    Week,
+    /// Since the 1st.
+    Month,
+    /// The whole history.
+    All,
+}
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            "all" => Ok(Period::All),
+            _ => Err(format!("expected day, week, month or all, got {s:?}")),
+        }
+    }
+}
+
+/// A point in time a report looks back to, taking what `--deadline` does.
+/// Bare times and weekdays mean their last occurrence rather than the next,
+/// so `--since 09:00` is this morning.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Past(pub DateTime<Local>);
+
+impl std::str::FromStr for Past {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        deadline::parse_past_datetime(s, Local::now()).map(Past)
+    }
+}
+
+impl Period {
+    fn start(self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let today = now.date_naive();
+        let date = match self {
+            Period::Day => today,
+            Period::Week => today - Days::new(today.weekday().num_days_from_monday() as u64),
+            Period::Month => today.with_day(1)?,
+            Period::All => return None,
+        };
+        date.and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+    }
+}
+
+/// What a report adds time up by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    #[default]
+    Timer,
+    Task,
+    /// One row per task of every timer.
+    Both,
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "timer" => Ok(GroupBy::Timer),
+            "task" => Ok(GroupBy::Task),
+            "both" => Ok(GroupBy::Both),
+            _ => Err(format!("expected timer, task or both, got {s:?}")),
+        }
+    }
+}
+
+impl GroupBy {
+    fn key(self, timer: &str, task: &str) -> String {
+        match self {
+            GroupBy::Timer => timer.to_string(),
+            GroupBy::Task if task.is_empty() => "(no task)".to_string(),
+            GroupBy::Task => task.to_string(),
+            GroupBy::Both if task.is_empty() => timer.to_string(),
+            GroupBy::Both => format!("{timer} / {task}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Csv,
+    Markdown,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "csv" => Ok(ReportFormat::Csv),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            _ => Err(format!("expected text, csv or markdown, got {s:?}")),
+        }
+    }
+}
+
+#[derive(kmacros::FieldIter, Debug, Clone, Default)]
+#[field_iter(parse_iter_mut = "dyn Arg")]
+pub struct ReportOptions {
+    pub period: Period,
+    /// Overrides the start of `period`.
+    pub since: Option<Past>,
+    /// Defaults to now.
+    pub until: Option<Past>,
+    /// Round every total up to a multiple of this, e.g. `15m` or `6m`.
+    pub round: Duration,
+    pub by: GroupBy,
+    pub format: ReportFormat,
+    /// Defaults to `$XDG_STATE_HOME/osd-timer/history.csv`.
+    pub history_file: String,
+}
+
+/// Per-timer bookkeeping while replaying the history.
+#[derive(Debug, Default)]
+struct Tally {
+    /// Where the time in the current run has been counted up to.
+    running_since: Option<DateTime<Local>>,
+    /// The timer's elapsed time after the previous event.
+    elapsed: Duration,
+    /// Its elapsed time when it was last cleared, which a restore brings back.
+    cleared: Duration,
+    /// The task its time is currently booked to.
+    task: String,
+}
+
+/// How much of `from..to` falls inside `since..until`.
+fn overlap(
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+    since: Option<DateTime<Local>>,
+    until: DateTime<Local>,
+) -> chrono::Duration {
+    let from = since.map_or(from, |since| from.max(since));
+    let to = to.min(until);
+    (to - from).max(chrono::Duration::zero())
+}
+
+/// Adds up the time each timer ran within `since..until`, grouped by `by`.
+/// Runs are the spans between starting and stopping a timer; increments,
+/// decrements, typed-in times and undos count at the moment they were made.
+/// Clearing a timer doesn't take back its time, and neither does undoing the
+/// clear add it again.
+fn totals(
+    events: &[Event],
+    since: Option<DateTime<Local>>,
+    until: DateTime<Local>,
+    by: GroupBy,
+) -> BTreeMap<String, Duration> {
+    let mut tallies: BTreeMap<&str, Tally> = BTreeMap::new();
+    // In ms, signed since a decrement can take off more than was counted so far.
+    let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+    for event in events {
+        let tally = tallies.entry(&event.timer).or_default();
+        let total = totals.entry(by.key(&event.timer, &tally.task)).or_default();
+        let mut ran = chrono::Duration::zero();
+        if let Some(running_since) = tally.running_since {
+            ran = event.at - running_since;
+            *total += overlap(running_since, event.at, since, until).num_milliseconds();
+        }
+        let in_range = since.is_none_or(|since| event.at >= since) && event.at < until;
+        let adjusted = matches!(
+            event.action,
+            Action::Increment
+                | Action::Decrement
+                | Action::Set
+                | Action::Undo
+                | Action::Redo
+                | Action::Restore
+        );
+        // The cleared time was billed when it was cleared, so only what
+        // comes back on top of it counts again.
+        let baseline = if event.action == Action::Restore {
+            tally.cleared
+        } else {
+            tally.elapsed
+        };
+        if adjusted && in_range {
+            // Whatever changed beyond the time it ran was a manual step.
+            let step = event.elapsed.as_millis() as i64
+                - baseline.as_millis() as i64
+                - ran.num_milliseconds();
+            // Rounded to whole seconds to drop the jitter between clocks.
+            let step = (step as f64 / 1000.0).round() as i64;
+            *total += step * 1000;
+        }
+        tally.running_since = match event.action {
+            Action::Start | Action::Restart => Some(event.at),
+            Action::Stop | Action::Clear | Action::Reset => None,
+            _ => tally.running_since.map(|_| event.at),
+        };
+        if event.action == Action::Clear {
+            tally.cleared = event.elapsed;
+        }
+        tally.elapsed = match event.action {
+            Action::Clear | Action::Restart | Action::Reset => Duration::ZERO,
+            _ => event.elapsed,
+        };
+        tally.task.clone_from(&event.task);
+    }
+    // Timers still running count up to now.
+    let now = Local::now();
+    for (timer, tally) in tallies {
+        if let Some(running_since) = tally.running_since {
+            *totals.entry(by.key(timer, &tally.task)).or_default() +=
+                overlap(running_since, now, since, until).num_milliseconds();
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(key, total)| (key, Duration::from_millis(total.max(0) as u64)))
+        .filter(|(_, total)| !total.is_zero())
+        .collect()
+}
+
+fn round_up(duration: Duration, increment: Duration) -> Duration {
+    if increment.is_zero() {
+        return duration;
+    }
+    let increment = increment.as_millis();
+    Duration::from_millis((duration.as_millis().div_ceil(increment) * increment) as u64)
+}
+
+/// `H:MM:SS`, with as many hour digits as it takes.
+fn hms(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn hours(duration: Duration) -> String {
+    format!("{:.2}", duration.as_secs_f64() / 3600.0)
+}
+
+fn print(rows: &[(String, Duration)], by: GroupBy, format: ReportFormat) {
+    let heading = match by {
+        GroupBy::Timer => "timer",
+        GroupBy::Task => "task",
+        GroupBy::Both => "timer / task",
+    };
+    let total: Duration = rows.iter().map(|(_, duration)| *duration).sum();
+    match format {
+        ReportFormat::Text => {
+            let width = rows
+                .iter()
+                .map(|(timer, _)| timer.chars().count())
+                .chain(["total".len()])
+                .max()
+                .unwrap_or_default();
+            for (timer, duration) in rows {
+                println!(
+                    "{timer:width$}  {:>10}  {:>8}",
+                    hms(*duration),
+                    hours(*duration)
+                );
+            }
+            println!(
+                "{:width$}  {:>10}  {:>8}",
+                "total",
+                hms(total),
+                hours(total)
+            );
+        }
+        ReportFormat::Csv => {
+            println!("{},duration,hours", history::csv_field(heading));
+            for (timer, duration) in rows {
+                let timer = history::csv_field(timer);
+                println!("{timer},{},{}", hms(*duration), hours(*duration));
+            }
+        }
+        ReportFormat::Markdown => {
+            println!("| {heading} | duration | hours |");
+            println!("| --- | ---: | ---: |");
+            for (timer, duration) in rows {
+                let timer = timer.replace('|', "\\|");
+                println!("| {timer} | {} | {} |", hms(*duration), hours(*duration));
+            }
+            println!("| **Total** | **{}** | **{}** |", hms(total), hours(total));
+        }
+    }
+}
+
+/// `osd-timer report [--period day|week|month|all] [--since T] [--until T]
+/// [--round 15m] [--by timer|task|both] [--format text|csv|markdown]
+/// [--history_file PATH]`
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut options = ReportOptions::default();
+    crate::parse_fields(args, |f| options.parse_iter_mut(f));
+    let path = if options.history_file.is_empty() {
+        history::default_path().ok_or("no history file, set --history_file")?
+    } else {
+        options.history_file.clone().into()
+    };
+    let (events, skipped) = history::read(&path)?;
+    for err in skipped {
+        eprintln!("Skipped a history row: {err}");
+    }
+    let now = Local::now();
+    let since = options
+        .since
+        .map(|since| since.0)
+        .or_else(|| options.period.start(now));
+    let until = options.until.map_or(now, |until| until.0);
+    let rows: Vec<(String, Duration)> = totals(&events, since, until, options.by)
+        .into_iter()
+        .map(|(timer, total)| (timer, round_up(total, options.round)))
+        .collect();
+    print(&rows, options.by, options.format);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn base() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 10, 9, 0, 0).unwrap()
+    }
+
+    /// `action` on `timer`, `at` seconds after [`base`].
+    fn event(at: i64, timer: &str, action: Action, elapsed: u64, task: &str) -> Event {
+        Event {
+            at: base() + chrono::Duration::seconds(at),
+            timer: timer.to_string(),
+            action,
+            elapsed: Duration::from_secs(elapsed),
+            task: task.to_string(),
+        }
+    }
+
+    /// Totals in seconds, for everything up to an hour after [`base`].
+    fn secs(events: &[Event], since: Option<i64>, by: GroupBy) -> Vec<(String, u64)> {
+        let since = since.map(|since| base() + chrono::Duration::seconds(since));
+        let until = base() + chrono::Duration::hours(1);
+        totals(events, since, until, by)
+            .into_iter()
+            .map(|(key, total)| (key, total.as_secs()))
+            .collect()
+    }
+
+    fn total(events: &[Event]) -> u64 {
+        match &secs(events, None, GroupBy::Timer)[..] {
+            [] => 0,
+            [(_, total)] => *total,
+            totals => panic!("{totals:?}"),
+        }
+    }
+
+    #[test]
+    fn runs_count_from_start_to_stop() {
+        use Action::*;
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(600, "a", Stop, 600, ""),
+            event(900, "a", Start, 600, ""),
+            event(1000, "a", Stop, 700, ""),
+        ];
+        assert_eq!(total(&events), 700);
+        // Only the part after `since` counts.
+        assert_eq!(
+            secs(&events, Some(300), GroupBy::Timer),
+            [("a".to_string(), 400)]
+        );
+        // A timer that is still running counts up to `until`.
+        assert_eq!(total(&[event(0, "a", Start, 0, "")]), 3600);
+    }
+
+    #[test]
+    fn increments_and_decrements_count_when_made() {
+        use Action::*;
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(300, "a", Increment, 360, ""),
+            event(600, "a", Stop, 660, ""),
+            event(700, "a", Decrement, 560, ""),
+        ];
+        assert_eq!(total(&events), 560);
+        // Taking off more than was counted doesn't go below zero.
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(60, "a", Stop, 60, ""),
+            event(100, "a", Decrement, 0, ""),
+            event(200, "a", Set, 0, ""),
+        ];
+        assert_eq!(total(&events), 0);
+    }
+
+    #[test]
+    fn undo_counts_like_the_step_it_takes_back() {
+        use Action::*;
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(600, "a", Stop, 600, ""),
+            event(700, "a", Increment, 900, ""),
+            event(800, "a", Undo, 600, ""),
+            event(900, "a", Redo, 900, ""),
+        ];
+        assert_eq!(total(&events), 900);
+    }
+
+    #[test]
+    fn clearing_keeps_the_time_and_restoring_doesnt_add_it_again() {
+        use Action::*;
+        let cleared = [
+            event(0, "a", Start, 0, ""),
+            event(600, "a", Clear, 600, ""),
+            event(700, "a", Start, 0, ""),
+            event(800, "a", Stop, 100, ""),
+        ];
+        assert_eq!(total(&cleared), 700);
+        let undone = [
+            event(0, "a", Start, 0, ""),
+            event(600, "a", Stop, 600, ""),
+            event(700, "a", Clear, 600, ""),
+            event(800, "a", Restore, 600, ""),
+            event(850, "a", Clear, 600, ""),
+            event(900, "a", Restore, 600, ""),
+        ];
+        assert_eq!(total(&undone), 600);
+        // A restored stopwatch that was running gets the time since the clear.
+        let running = [
+            event(0, "a", Start, 0, ""),
+            event(600, "a", Clear, 600, ""),
+            event(660, "a", Restore, 660, ""),
+            event(660, "a", Start, 660, ""),
+            event(720, "a", Stop, 720, ""),
+        ];
+        assert_eq!(total(&running), 720);
+    }
+
+    #[test]
+    fn since_a_time_of_day_that_passed_is_today() {
+        use Action::*;
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(1800, "a", Stop, 1800, ""),
+        ];
+        // `--since 09:00` at 10:00.
+        let now = base() + chrono::Duration::hours(1);
+        let since = deadline::parse_past_datetime("09:00", now).unwrap();
+        assert_eq!(since, base());
+        let totals: Vec<_> = totals(&events, Some(since), now, GroupBy::Timer)
+            .into_iter()
+            .collect();
+        assert_eq!(totals, [("a".to_string(), Duration::from_secs(1800))]);
+        let Past(since) = "00:00".parse().unwrap();
+        assert!(since <= Local::now());
+    }
+
+    #[test]
+    fn groups_by_task() {
+        use Action::*;
+        let events = [
+            event(0, "a", Start, 0, ""),
+            event(300, "a", Task, 300, "email"),
+            event(600, "a", Stop, 600, "email"),
+            event(0, "b", Start, 0, "email"),
+            event(100, "b", Stop, 100, "email"),
+        ];
+        assert_eq!(
+            secs(&events, None, GroupBy::Task),
+            [("(no task)".to_string(), 300), ("email".to_string(), 400)]
+        );
+        assert_eq!(
+            secs(&events, None, GroupBy::Both),
+            [
+                ("a".to_string(), 300),
+                ("a / email".to_string(), 300),
+                ("b / email".to_string(), 100),
+            ]
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Timelike};
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A recurring point in time, at minute resolution.
+///
+/// Each field is a bitmask of the allowed values, with the same meaning as
+/// the corresponding cron field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    /// Cron matches either day field when both are restricted.
+    any_day: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .map_err(|err| format!("{step:?}: {err}"))?;
+                (range, step.max(1))
+            }
+            None => (part, 1),
+        };
+        let parse = |s: &str| -> Result<u32, String> {
+            let value = s.parse::<u32>().map_err(|err| format!("{s:?}: {err}"))?;
+            if !(min..=max).contains(&value) {
+                return Err(format!("{value} is outside {min}-{max}"));
+            }
+            Ok(value)
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None if step > 1 => (parse(range)?, max),
+                None => (parse(range)?, parse(range)?),
+            },
+        };
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_days(days: &str) -> Option<u8> {
+    match days {
+        "daily" => return Some(0b111_1111),
+        "weekdays" => return Some(0b011_1110),
+        "weekends" => return Some(0b100_0001),
+        _ => (),
+    }
+    let mut mask = 0;
+    for day in days.split(',') {
+        let day = day.to_ascii_lowercase();
+        let index = DAY_NAMES.iter().position(|name| day.starts_with(name))?;
+        mask |= 1 << index;
+    }
+    Some(mask)
+}
+
+impl Schedule {
+    /// Parses `daily 09:30`, `weekdays 17:00`, `weekends 10:00`,
+    /// `mon,wed,fri 09:30` or a 5-field cron expression
+    /// (`minute hour day-of-month month day-of-week`).
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let schedule = match fields[..] {
+            [days, time] => {
+                let days = parse_days(days).ok_or_else(|| format!("unknown days {days:?}"))?;
+                let time = NaiveTime::parse_from_str(time, "%H:%M")
+                    .map_err(|err| format!("{time:?}: {err}"))?;
+                Self {
+                    minutes: 1 << time.minute(),
+                    hours: 1 << time.hour(),
+                    // The same masks `*` gives, so both forms compare equal.
+                    days_of_month: !1,
+                    months: 0b1_1111_1111_1110,
+                    days_of_week: days,
+                    any_day: false,
+                }
+            }
+            [minutes, hours, days_of_month, months, days_of_week] => {
+                let mut dow = parse_field(days_of_week, 0, 7)? as u8;
+                // Both 0 and 7 mean Sunday.
+                if dow & 0b1000_0000 != 0 {
+                    dow = (dow | 1) & 0b111_1111;
+                }
+                Self {
+                    minutes: parse_field(minutes, 0, 59)?,
+                    hours: parse_field(hours, 0, 23)? as u32,
+                    days_of_month: parse_field(days_of_month, 1, 31)? as u32,
+                    months: parse_field(months, 1, 12)? as u16,
+                    days_of_week: dow,
+                    any_day: days_of_month != "*" && days_of_week != "*",
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "expected \"<days> HH:MM\" or 5 cron fields, got {s:?}"
+                ))
+            }
+        };
+        if schedule.next_after(Local::now()).is_none() {
+            return Err(format!("{s:?} never occurs"));
+        }
+        Ok(schedule)
+    }
+
+    fn matches_day(&self, date: chrono::NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let dom = self.days_of_month & (1 << date.day()) != 0;
+        let dow = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.any_day {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// The first occurrence strictly after `now`, looking at most a few years
+    /// ahead.
+    pub fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut date = now.date_naive();
+        for _ in 0..366 * 5 {
+            if self.matches_day(date) {
+                for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                    for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                        let Some(at) = date
+                            .and_hms_opt(hour, minute, 0)
+                            .and_then(|t| t.and_local_timezone(Local).earliest())
+                        else {
+                            continue;
+                        };
+                        if at > now {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date += Duration::days(1);
+        }
+        None
+    }
+}
+
+/// Writes the values set in `mask` between `min` and `max` as a cron list,
+/// or `*` if `wildcard` is set and all of them are.
+fn write_field(
+    f: &mut std::fmt::Formatter<'_>,
+    mask: u64,
+    min: u32,
+    max: u32,
+    wildcard: bool,
+) -> std::fmt::Result {
+    let values: Vec<u32> = (min..=max).filter(|v| mask & (1 << v) != 0).collect();
+    if wildcard && values.len() as u32 == max - min + 1 {
+        return f.write_str("*");
+    }
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            f.write_str(",")?;
+        }
+        write!(f, "{value}")?;
+    }
+    Ok(())
+}
+
+/// Writes the schedule as a 5-field cron expression that parses back to the
+/// same schedule.
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_field(f, self.minutes, 0, 59, true)?;
+        f.write_str(" ")?;
+        write_field(f, self.hours as u64, 0, 23, true)?;
+        f.write_str(" ")?;
+        // A `*` day field would turn off `any_day` when parsed back.
+        write_field(f, self.days_of_month as u64, 1, 31, !self.any_day)?;
+        f.write_str(" ")?;
+        write_field(f, self.months as u64, 1, 12, true)?;
+        f.write_str(" ")?;
+        write_field(f, self.days_of_week as u64, 0, 6, !self.any_day)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// A local time on the minute, also for the deadline tests.
+    pub(crate) fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_day_names() {
+        assert_eq!(
+            Schedule::parse("daily 09:30"),
+            Schedule::parse("30 9 * * *")
+        );
+        assert_eq!(
+            Schedule::parse("weekdays 17:00"),
+            Schedule::parse("0 17 * * 1-5")
+        );
+        assert_eq!(
+            Schedule::parse("weekends 10:00"),
+            Schedule::parse("0 10 * * 0,6")
+        );
+        assert_eq!(
+            Schedule::parse("mon,wed,fri 09:30"),
+            Schedule::parse("30 9 * * 1,3,5")
+        );
+        assert_eq!(Schedule::parse("0 0 * * 7"), Schedule::parse("0 0 * * 0"));
+    }
+
+    #[test]
+    fn rejects_bad_schedules() {
+        assert!(Schedule::parse("daily").is_err());
+        assert!(Schedule::parse("someday 09:30").is_err());
+        assert!(Schedule::parse("daily 25:00").is_err());
+        assert!(Schedule::parse("60 * * * *").is_err());
+        assert!(Schedule::parse("0 0 31 2 *").is_err());
+    }
+
+    #[test]
+    fn next_after_is_strictly_later() {
+        let schedule = Schedule::parse("daily 09:30").unwrap();
+        // 2024-01-10 is a Wednesday.
+        assert_eq!(
+            schedule.next_after(at(2024, 1, 10, 8, 0)),
+            Some(at(2024, 1, 10, 9, 30))
+        );
+        assert_eq!(
+            schedule.next_after(at(2024, 1, 10, 9, 30)),
+            Some(at(2024, 1, 11, 9, 30))
+        );
+    }
+
+    #[test]
+    fn next_after_skips_to_matching_days() {
+        let weekdays = Schedule::parse("weekdays 17:00").unwrap();
+        assert_eq!(
+            weekdays.next_after(at(2024, 1, 12, 18, 0)),
+            Some(at(2024, 1, 15, 17, 0))
+        );
+        let every_15 = Schedule::parse("*/15 9-10 * * *").unwrap();
+        assert_eq!(
+            every_15.next_after(at(2024, 1, 10, 9, 20)),
+            Some(at(2024, 1, 10, 9, 30))
+        );
+        assert_eq!(
+            every_15.next_after(at(2024, 1, 10, 10, 45)),
+            Some(at(2024, 1, 11, 9, 0))
+        );
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_set() {
+        // The 1st of the month or any Monday.
+        let schedule = Schedule::parse("0 12 1 * 1").unwrap();
+        assert_eq!(
+            schedule.next_after(at(2024, 1, 10, 0, 0)),
+            Some(at(2024, 1, 15, 12, 0))
+        );
+        assert_eq!(
+            schedule.next_after(at(2024, 1, 29, 13, 0)),
+            Some(at(2024, 2, 1, 12, 0))
+        );
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for s in [
+            "daily 09:30",
+            "weekdays 17:00",
+            "mon,wed,fri 09:30",
+            "*/15 9-17 * * 1-5",
+            "0 12 1 * 1",
+            "0 0 1,15 1-6 *",
+        ] {
+            let schedule = Schedule::parse(s).unwrap();
+            assert_eq!(Schedule::parse(&schedule.to_string()), Ok(schedule), "{s}");
+        }
+        assert_eq!(
+            Schedule::parse("weekdays 17:00").unwrap().to_string(),
+            "0 17 * * 1,2,3,4,5"
+        );
+    }
+}
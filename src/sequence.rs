@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use crate::countdown::Countdown;
+use crate::duration::parse_duration;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Step {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// The steps of an interval sequence with every repeat already expanded.
+#[derive(Debug, Clone, Default)]
+pub struct Steps(pub Vec<Step>);
+
+impl Steps {
+    /// Parses a sequence such as `warmup 5m, (work 40s, rest 20s) x8, cooldown 5m`.
+    ///
+    /// Steps are separated by commas or newlines and are a name followed by a
+    /// duration. A trailing `xN` (or `×N`, `*N`) repeats the parenthesised
+    /// group before it. After steps joined by commas it repeats the last two
+    /// of them, the usual work and rest, so `warmup 5m, work 40s, rest 20s x8,
+    /// cooldown 5m` is eight rounds of work and rest between warmup and
+    /// cooldown. A step on its own, or the first on its line, repeats alone.
+    /// Lines starting with `#` are ignored.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(s).into_iter().peekable();
+        let steps = parse_items(&mut tokens)?;
+        if let Some(token) = tokens.next() {
+            return Err(format!("unexpected {token:?} in sequence"));
+        }
+        if steps.is_empty() {
+            return Err("empty sequence".to_string());
+        }
+        Ok(Self(steps))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Separator,
+    /// A separator that keeps the steps on the same line.
+    Comma,
+    Word(String),
+}
+
+type Tokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
+
+fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    for line in s.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut word = String::new();
+        for c in line.chars().chain(std::iter::once('\n')) {
+            let token = match c {
+                '(' => Some(Token::Open),
+                ')' => Some(Token::Close),
+                ',' => Some(Token::Comma),
+                '\n' => Some(Token::Separator),
+                c if c.is_whitespace() => None,
+                c => {
+                    word.push(c);
+                    continue;
+                }
+            };
+            if !word.is_empty() {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+            }
+            tokens.extend(token);
+        }
+    }
+    tokens
+}
+
+fn parse_repeat(word: &str) -> Option<usize> {
+    word.strip_prefix(['x', '×', '*'])?.parse().ok()
+}
+
+fn parse_items(tokens: &mut Tokens) -> Result<Vec<Step>, String> {
+    let mut steps = vec![];
+    // Where the steps joined by commas that a repeat can cover start.
+    let mut run_start = 0;
+    loop {
+        match tokens.peek() {
+            None | Some(Token::Close) => return Ok(steps),
+            Some(Token::Comma) => {
+                tokens.next();
+            }
+            Some(Token::Separator) => {
+                tokens.next();
+                run_start = steps.len();
+            }
+            Some(Token::Open) => {
+                tokens.next();
+                let group = parse_items(tokens)?;
+                if tokens.next() != Some(Token::Close) {
+                    return Err("missing ')' in sequence".to_string());
+                }
+                let repeat = parse_item_repeat(tokens)?;
+                for _ in 0..repeat {
+                    steps.extend_from_slice(&group);
+                }
+                run_start = steps.len();
+            }
+            Some(Token::Word(_)) => {
+                let mut words = vec![];
+                while let Some(Token::Word(word)) = tokens.peek() {
+                    if parse_repeat(word).is_some() && !words.is_empty() {
+                        break;
+                    }
+                    words.push(word.clone());
+                    tokens.next();
+                }
+                let duration = words.pop().unwrap_or_default();
+                let step = Step {
+                    name: words.join(" "),
+                    duration: parse_duration(&duration)
+                        .map_err(|err| format!("bad step duration {duration:?}: {err}"))?,
+                };
+                steps.push(step);
+                if matches!(tokens.peek(), Some(Token::Word(_))) {
+                    let repeat = parse_item_repeat(tokens)?;
+                    let block = steps.split_off(steps.len().saturating_sub(2).max(run_start));
+                    for _ in 0..repeat {
+                        steps.extend_from_slice(&block);
+                    }
+                    run_start = steps.len();
+                }
+            }
+        }
+    }
+}
+
+fn parse_item_repeat(tokens: &mut Tokens) -> Result<usize, String> {
+    match tokens.peek() {
+        Some(Token::Word(word)) => {
+            let repeat =
+                parse_repeat(word).ok_or_else(|| format!("expected a repeat at {word:?}"))?;
+            tokens.next();
+            Ok(repeat)
+        }
+        _ => Ok(1),
+    }
+}
+
+/// Where a running [`Sequence`] currently is.
+#[derive(Debug, Clone, Copy)]
+pub struct Position<'a> {
+    pub index: usize,
+    pub step: &'a Step,
+    /// Remaining lead-in before the step itself starts, if still counting it down.
+    pub lead_in: Option<Duration>,
+    /// Time left in the step, not counting any lead-in.
+    pub remaining: Duration,
+}
+
+/// Runs `steps` back to back, each optionally preceded by a `lead_in`
+/// countdown. The whole sequence is timed by one [`Countdown`] and the current
+/// step is derived from its elapsed time.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub steps: Vec<Step>,
+    pub lead_in: Duration,
+    pub countdown: Countdown,
+}
+
+impl Sequence {
+    pub fn new(steps: &Steps, repeat: usize, lead_in: Duration) -> Self {
+        let steps: Vec<Step> = std::iter::repeat_n(&steps.0, repeat.max(1))
+            .flatten()
+            .cloned()
+            .collect();
+        let total = steps.iter().map(|step| lead_in + step.duration).sum();
+        Self {
+            steps,
+            lead_in,
+            countdown: Countdown::new(total),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.countdown.is_running()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.countdown.is_finished()
+    }
+
+    pub fn toggle(&mut self) {
+        self.countdown.toggle();
+    }
+
+    pub fn restart(&mut self) {
+        self.countdown.restart();
+    }
+
+    /// Overall time left, including lead-ins.
+    pub fn remaining(&self) -> Duration {
+        self.countdown.remaining()
+    }
+
+    pub fn position(&self) -> Option<Position<'_>> {
+        let mut elapsed = self.countdown.stopwatch.elapsed();
+        for (index, step) in self.steps.iter().enumerate() {
+            if elapsed < self.lead_in {
+                return Some(Position {
+                    index,
+                    step,
+                    lead_in: Some(self.lead_in - elapsed),
+                    remaining: step.duration,
+                });
+            }
+            elapsed -= self.lead_in;
+            if elapsed < step.duration {
+                return Some(Position {
+                    index,
+                    step,
+                    lead_in: None,
+                    remaining: step.duration - elapsed,
+                });
+            }
+            elapsed -= step.duration;
+        }
+        None
+    }
+
+    pub fn next_step(&self) -> Option<&Step> {
+        self.steps.get(self.position()?.index + 1)
+    }
+
+    /// Jumps to the start of the next step (or its lead-in).
+    pub fn skip(&mut self) {
+        if let Some(position) = self.position() {
+            let skipped = position.lead_in.unwrap_or_default() + position.remaining;
+            self.countdown.stopwatch.increment(skipped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The step names and their lengths in seconds.
+    fn parse(s: &str) -> Vec<(String, u64)> {
+        Steps::parse(s)
+            .unwrap_or_else(|err| panic!("{err}"))
+            .0
+            .into_iter()
+            .map(|step| (step.name, step.duration.as_secs()))
+            .collect()
+    }
+
+    fn steps(steps: &[(&str, u64)]) -> Vec<(String, u64)> {
+        steps
+            .iter()
+            .map(|(name, secs)| (name.to_string(), *secs))
+            .collect()
+    }
+
+    #[test]
+    fn trailing_repeat_covers_work_and_rest() {
+        let mut expected = vec![("warmup", 300)];
+        expected.extend([("work", 40), ("rest", 20)].repeat(8));
+        expected.push(("cooldown", 300));
+        assert_eq!(
+            parse("warmup 5m, work 40s, rest 20s ×8, cooldown 5m"),
+            steps(&expected)
+        );
+        assert_eq!(
+            parse("work 40s, rest 20s x2"),
+            steps(&[("work", 40), ("rest", 20), ("work", 40), ("rest", 20)])
+        );
+        // A step on its own repeats alone.
+        assert_eq!(
+            parse("rest 20s *3, work 1m"),
+            steps(&[("rest", 20), ("rest", 20), ("rest", 20), ("work", 60)])
+        );
+        assert_eq!(
+            parse("a 1s x2, b 2s x2"),
+            steps(&[("a", 1), ("a", 1), ("b", 2), ("b", 2)])
+        );
+    }
+
+    #[test]
+    fn groups_repeat_as_a_whole() {
+        assert_eq!(
+            parse("warmup 5m, (work 40s, rest 20s) x2, long stretch 1m"),
+            steps(&[
+                ("warmup", 300),
+                ("work", 40),
+                ("rest", 20),
+                ("work", 40),
+                ("rest", 20),
+                ("long stretch", 60),
+            ])
+        );
+        assert_eq!(
+            parse("(a 1s, (b 2s) x2) x2"),
+            steps(&[("a", 1), ("b", 2), ("b", 2), ("a", 1), ("b", 2), ("b", 2)])
+        );
+        assert_eq!(
+            parse("warmup 1m, (sprint 10s) x3"),
+            steps(&[
+                ("warmup", 60),
+                ("sprint", 10),
+                ("sprint", 10),
+                ("sprint", 10)
+            ])
+        );
+    }
+
+    #[test]
+    fn lines_are_separate_steps() {
+        let file = "# intervals\nwarmup 5m\nwork 40s\nrest 20s x2\n\ncooldown 5m\n";
+        assert_eq!(
+            parse(file),
+            steps(&[
+                ("warmup", 300),
+                ("work", 40),
+                ("rest", 20),
+                ("rest", 20),
+                ("cooldown", 300),
+            ])
+        );
+    }
+
+    #[test]
+    fn zero_repeats_drop_the_steps() {
+        assert_eq!(
+            parse("warmup 5m, work 40s, rest 20s x0, cooldown 5m"),
+            steps(&[("warmup", 300), ("cooldown", 300)])
+        );
+        assert_eq!(parse("a 1s, (b 2s) x0"), steps(&[("a", 1)]));
+        assert!(Steps::parse("a 1s x0").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_sequences() {
+        assert!(Steps::parse("").is_err());
+        assert!(Steps::parse("work forever").is_err());
+        assert!(Steps::parse("(work 40s, rest 20s x2").is_err());
+        assert!(Steps::parse("work 40s) x2").is_err());
+        assert!(Steps::parse("work 40s x2 x3").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// What a signal asks the main loop to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// `SIGUSR1`, start or pause the active stopwatch.
+    Toggle,
+    /// `SIGUSR2`, clear the active stopwatch.
+    Reset,
+    /// `SIGHUP`, reread the state file and the files the command line points
+    /// at: a `--sequence @path` whose file changed starts over with the new
+    /// steps. The command line itself can't have changed, so deadlines keep
+    /// running as they are and the other options stay as they were at
+    /// startup.
+    Reload,
+    /// `SIGTERM` or `SIGINT`, save and exit.
+    Quit,
+}
+
+const SIGNALS: [(libc::c_int, Signal); 5] = [
+    (libc::SIGUSR1, Signal::Toggle),
+    (libc::SIGUSR2, Signal::Reset),
+    (libc::SIGHUP, Signal::Reload),
+    (libc::SIGTERM, Signal::Quit),
+    (libc::SIGINT, Signal::Quit),
+];
+
+/// One bit per entry of `SIGNALS` that arrived since the last [`take`].
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+extern "C" fn handle(signal: libc::c_int) {
+    // Setting a bit is all that is safe to do here, the main loop does the rest.
+    if let Some(index) = SIGNALS.iter().position(|(known, _)| *known == signal) {
+        PENDING.fetch_or(1 << index, Ordering::SeqCst);
+    }
+}
+
+/// Catches the signals in [`Signal`] from now on, instead of letting them kill
+/// the process.
+pub fn install() -> io::Result<()> {
+    for (signal, _) in SIGNALS {
+        // SAFETY: `handle` only touches an atomic, and `action` is fully
+        // initialized before it is passed on.
+        let result = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut())
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// The signals that arrived since the last call, each at most once.
+pub fn take() -> impl Iterator<Item = Signal> {
+    let pending = PENDING.swap(0, Ordering::SeqCst);
+    SIGNALS
+        .into_iter()
+        .enumerate()
+        .filter(move |(index, _)| pending & (1 << index) != 0)
+        .map(|(_, (_, signal))| signal)
+}
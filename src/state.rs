@@ -0,0 +1,314 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::deadline::Deadline;
+use crate::stopwatch::Stopwatch;
+use crate::{Arg, Labeled};
+
+const HEADER: &str = "# osd-timer state v1";
+
+/// What survives a restart: stopwatches (including whether they were
+/// running) and deadlines that weren't given on the command line.
+///
+/// The file is line based, with one record per line and its fields
+/// separated by tabs:
+///
+/// - `active`, the index of the active stopwatch.
+/// - `stopwatch`, its total in ms, when it was started in unix ms (or `-`
+///   while paused), its splits in ms separated by commas, and its name.
+/// - `task`, time booked to a task of the stopwatch before it, and the task.
+/// - `current_task`, that stopwatch's elapsed time when its current task was
+///   set, and the task.
+/// - `deadline`, as `label=deadline`.
+/// - `dropped_deadline`, the index of a `--deadline` that was moved, edited
+///   or removed, and its label.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub active_stopwatch: usize,
+    pub stopwatches: Vec<Stopwatch>,
+    pub deadlines: Vec<Labeled<Deadline>>,
+    /// Deadlines from the command line that shouldn't come back, by index,
+    /// with their label to tell if the command line changed since.
+    pub dropped_deadlines: Vec<(usize, String)>,
+}
+
+/// `$XDG_STATE_HOME/osd-timer`, falling back to `~/.local/state`.
+pub fn dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+        })?;
+    Some(dir.join("osd-timer"))
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(dir()?.join("state"))
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+impl State {
+    /// Reads the state saved at `path`. A missing file is an empty state.
+    /// Records that don't parse are skipped, so one bad line doesn't lose
+    /// the rest; they come back as errors next to the state.
+    pub fn load(path: &Path) -> Result<(Self, Vec<String>), String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        };
+        let mut state = Self::default();
+        let mut skipped = vec![];
+        // The task records of a skipped stopwatch would land on the one
+        // before it, so they go too.
+        let mut skipping_tasks = false;
+        for (index, line) in text.lines().enumerate() {
+            let kind = line.split('\t').next().unwrap_or_default();
+            if skipping_tasks && matches!(kind, "task" | "current_task") {
+                continue;
+            }
+            if kind == "stopwatch" {
+                skipping_tasks = false;
+            }
+            if let Err(err) = state.parse_line(line) {
+                skipping_tasks |= kind == "stopwatch";
+                skipped.push(format!("{}:{}: {err}", path.display(), index + 1));
+            }
+        }
+        Ok((state, skipped))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let parse_ms = |s: &str| {
+            s.parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|err| format!("{s:?}: {err}"))
+        };
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+        let (kind, rest) = line.split_once('\t').unwrap_or((line, ""));
+        match kind {
+            "active" => {
+                self.active_stopwatch = rest.parse().map_err(|err| format!("{rest:?}: {err}"))?;
+            }
+            "stopwatch" => {
+                let [total, started_at, splits, name] =
+                    rest.splitn(4, '\t').collect::<Vec<_>>()[..]
+                else {
+                    return Err(format!("expected 4 fields, got {rest:?}"));
+                };
+                let mut stopwatch = Stopwatch::new(name);
+                stopwatch.total = parse_ms(total)?;
+                if started_at != "-" {
+                    // Time spent running before the restart is folded into
+                    // the total, so it keeps counting from here.
+                    let started_at = UNIX_EPOCH + parse_ms(started_at)?;
+                    stopwatch.total += SystemTime::now()
+                        .duration_since(started_at)
+                        .unwrap_or_default();
+                    stopwatch.start = Some(Instant::now());
+                }
+                stopwatch.splits = splits
+                    .split(',')
+                    .filter(|split| !split.is_empty())
+                    .map(parse_ms)
+                    .collect::<Result<_, _>>()?;
+                self.stopwatches.push(stopwatch);
+            }
+            "task" | "current_task" => {
+                let (ms, task) = rest.split_once('\t').unwrap_or((rest, ""));
+                let Some(stopwatch) = self.stopwatches.last_mut() else {
+                    return Err(format!("{kind} before any stopwatch"));
+                };
+                if kind == "task" {
+                    stopwatch
+                        .task_totals
+                        .push((task.to_string(), parse_ms(ms)?));
+                } else {
+                    stopwatch.task = task.to_string();
+                    stopwatch.task_mark = parse_ms(ms)?;
+                }
+            }
+            "deadline" => {
+                let mut deadline = Labeled::<Deadline>::default();
+                deadline.from_string(rest.to_string())?;
+                self.deadlines.push(deadline);
+            }
+            "dropped_deadline" => {
+                let (index, label) = rest.split_once('\t').unwrap_or((rest, ""));
+                let index = index.parse().map_err(|err| format!("{index:?}: {err}"))?;
+                self.dropped_deadlines.push((index, label.to_string()));
+            }
+            kind => return Err(format!("unknown record {kind:?}")),
+        }
+        Ok(())
+    }
+
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{HEADER}")?;
+        writeln!(out, "active\t{}", self.active_stopwatch)?;
+        for stopwatch in &self.stopwatches {
+            write!(out, "stopwatch\t{}\t", millis(stopwatch.total))?;
+            match stopwatch.start {
+                Some(start) => {
+                    let started_at = SystemTime::now() - start.elapsed();
+                    let since_epoch = started_at.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    write!(out, "{}", millis(since_epoch))?;
+                }
+                None => write!(out, "-")?,
+            }
+            write!(out, "\t")?;
+            for (index, split) in stopwatch.splits.iter().enumerate() {
+                if index > 0 {
+                    write!(out, ",")?;
+                }
+                write!(out, "{}", millis(*split))?;
+            }
+            writeln!(out, "\t{}", stopwatch.name.replace(['\t', '\n'], " "))?;
+            for (task, total) in &stopwatch.task_totals {
+                let task = task.replace(['\t', '\n'], " ");
+                writeln!(out, "task\t{}\t{task}", millis(*total))?;
+            }
+            let task = stopwatch.task.replace(['\t', '\n'], " ");
+            writeln!(out, "current_task\t{}\t{task}", millis(stopwatch.task_mark))?;
+        }
+        for deadline in &self.deadlines {
+            writeln!(out, "deadline\t{deadline}")?;
+        }
+        for (index, label) in &self.dropped_deadlines {
+            let label = label.replace(['\t', '\n'], " ");
+            writeln!(out, "dropped_deadline\t{index}\t{label}")?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the file at `path` in one step: the new state goes to a
+    /// temporary file next to it, which is flushed to disk and then renamed
+    /// over the old one. A crash at any point leaves either the old or the
+    /// new state behind, never a partial file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp)?;
+        let mut out = io::BufWriter::new(&mut file);
+        self.write(&mut out)?;
+        out.flush()?;
+        drop(out);
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file of its own under the temp dir, removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("osd-timer-test-{}-{name}", std::process::id()));
+            fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn load(name: &str, contents: &str) -> (State, Vec<String>) {
+        let file = TempFile::new(name, contents);
+        State::load(&file.0).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut paused = Stopwatch::new("work");
+        paused.total = Duration::from_millis(90_500);
+        paused.splits = vec![Duration::from_secs(30), Duration::from_secs(60)];
+        paused.task_totals = vec![("email".to_string(), Duration::from_secs(20))];
+        paused.task = "review".to_string();
+        paused.task_mark = Duration::from_secs(20);
+        let mut running = Stopwatch::new("break\ttime");
+        running.total = Duration::from_secs(5);
+        running.start = Some(Instant::now());
+        let mut deadline = Labeled::<Deadline>::default();
+        deadline
+            .from_string("standup=~2024-05-01T09:30:00+02:00".to_string())
+            .unwrap();
+        let state = State {
+            active_stopwatch: 1,
+            stopwatches: vec![paused, running],
+            deadlines: vec![deadline.clone()],
+            dropped_deadlines: vec![(0, String::new()), (2, "standup".to_string())],
+        };
+        let mut text = vec![];
+        state.write(&mut text).unwrap();
+        let (loaded, skipped) = load("round-trip", &String::from_utf8(text).unwrap());
+        assert!(skipped.is_empty(), "{skipped:?}");
+        assert_eq!(loaded.active_stopwatch, 1);
+        assert_eq!(loaded.deadlines, vec![deadline]);
+        assert_eq!(loaded.dropped_deadlines, state.dropped_deadlines);
+        let [paused, running] = &loaded.stopwatches[..] else {
+            panic!("{:?}", loaded.stopwatches);
+        };
+        assert_eq!(paused.name, "work");
+        assert_eq!(paused.total, Duration::from_millis(90_500));
+        assert!(paused.start.is_none());
+        assert_eq!(paused.splits, state.stopwatches[0].splits);
+        assert_eq!(paused.task_totals, state.stopwatches[0].task_totals);
+        assert_eq!(paused.task, "review");
+        assert_eq!(paused.task_mark, Duration::from_secs(20));
+        // Tabs would split the record, so they are written as spaces.
+        assert_eq!(running.name, "break time");
+        assert!(running.start.is_some());
+        assert!(running.elapsed() >= Duration::from_secs(5));
+        assert!(running.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        let path = std::env::temp_dir().join("osd-timer-test-does-not-exist");
+        let (state, skipped) = State::load(&path).unwrap();
+        assert!(state.stopwatches.is_empty() && skipped.is_empty());
+    }
+
+    #[test]
+    fn skips_bad_records() {
+        let (state, skipped) = load(
+            "bad-records",
+            "# osd-timer state v1\n\
+             active\t1\n\
+             stopwatch\t1000\t-\t\tgood\n\
+             current_task\t0\twriting\n\
+             stopwatch\tlots\t-\t\tbad\n\
+             task\t500\tlost\n\
+             current_task\t0\tlost\n\
+             bogus\n\
+             deadline\tnonsense=whenever\n\
+             stopwatch\t2000\t-\t500,1500\tgood too\n",
+        );
+        assert_eq!(skipped.len(), 3, "{skipped:?}");
+        let names: Vec<&str> = state.stopwatches.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["good", "good too"]);
+        // The tasks of the bad stopwatch don't end up on the one before it.
+        assert_eq!(state.stopwatches[0].task, "writing");
+        assert!(state.stopwatches[0].task_totals.is_empty());
+        assert_eq!(state.stopwatches[1].splits.len(), 2);
+        assert!(state.deadlines.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+/// A named stopwatch. `total` holds the time accumulated over previous runs,
+/// `start` is set while it is running.
+///
+/// `splits` holds the elapsed time at every lap mark, so lap `n` is
+/// `splits[n] - splits[n - 1]`.
+///
+/// Time is also booked to the current `task`: `task_totals` holds what
+/// earlier tasks (and earlier stints of this one) got, `task_mark` the elapsed
+/// time when the current task was set.
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    pub name: String,
+    pub total: Duration,
+    pub start: Option<Instant>,
+    pub splits: Vec<Duration>,
+    pub task: String,
+    pub task_totals: Vec<(String, Duration)>,
+    pub task_mark: Duration,
+}
+
+impl Stopwatch {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.start.is_some()
+    }
+
+    pub fn toggle(&mut self) {
+        self.start = match self.start {
+            Some(start) => {
+                self.total += start.elapsed();
+                None
+            }
+            None => Some(Instant::now()),
+        };
+    }
+
+    pub fn increment(&mut self, step: Duration) {
+        self.total += step;
+    }
+
+    pub fn decrement(&mut self, step: Duration) {
+        self.total -= self.total.min(step);
+        self.task_mark = self.task_mark.min(self.elapsed());
+    }
+
+    pub fn clear(&mut self) {
+        self.total = Duration::from_secs(0);
+        self.start = None;
+        self.splits.clear();
+        self.task_totals.clear();
+        self.task_mark = Duration::ZERO;
+    }
+
+    /// Books the time since the last switch to the current task, then
+    /// switches to `task`. The stopwatch itself keeps running as it was.
+    pub fn set_task(&mut self, task: impl Into<String>) {
+        let elapsed = self.elapsed();
+        let spent = elapsed.saturating_sub(self.task_mark);
+        let task = std::mem::replace(&mut self.task, task.into());
+        if !spent.is_zero() {
+            match self.task_totals.iter_mut().find(|(name, _)| *name == task) {
+                Some((_, total)) => *total += spent,
+                None => self.task_totals.push((task, spent)),
+            }
+        }
+        self.task_mark = elapsed;
+    }
+
+    /// Everything booked to `task` so far.
+    pub fn task_elapsed(&self, task: &str) -> Duration {
+        let booked = self
+            .task_totals
+            .iter()
+            .filter(|(name, _)| name == task)
+            .map(|(_, total)| *total)
+            .sum::<Duration>();
+        if task == self.task {
+            booked + self.elapsed().saturating_sub(self.task_mark)
+        } else {
+            booked
+        }
+    }
+
+    /// Sets the elapsed time outright, e.g. to fix a forgotten start. A
+    /// running stopwatch keeps running from there. Laps past the new time
+    /// are dropped.
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.total = elapsed;
+        self.start = self.start.map(|_| Instant::now());
+        self.splits.retain(|split| *split <= elapsed);
+        self.task_mark = self.task_mark.min(elapsed);
+    }
+
+    /// Marks the end of the current lap. Ignored while paused.
+    pub fn lap(&mut self) {
+        if self.is_running() {
+            self.splits.push(self.elapsed());
+        }
+    }
+
+    /// Completed lap durations, oldest first.
+    pub fn laps(&self) -> impl DoubleEndedIterator<Item = Duration> + ExactSizeIterator + '_ {
+        (0..self.splits.len()).map(|index| {
+            let previous = index
+                .checked_sub(1)
+                .map_or(Duration::ZERO, |i| self.splits[i]);
+            self.splits[index].saturating_sub(previous)
+        })
+    }
+
+    /// Time since the last lap mark, or the whole elapsed time if there is none.
+    pub fn current_lap(&self) -> Duration {
+        let last_split = self.splits.last().copied().unwrap_or_default();
+        self.elapsed().saturating_sub(last_split)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.total + self.start.map(|ts| ts.elapsed()).unwrap_or_default()
+    }
+}
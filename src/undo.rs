@@ -0,0 +1,47 @@
+/// Snapshots of some state, each labeled with what replaced it, so changes
+/// can be stepped back and forth. The label can carry more than a name for
+/// the change, for whoever needs to know what exactly is being undone.
+#[derive(Debug, Clone)]
+pub struct UndoStack<T, L = String> {
+    undo: Vec<(L, T)>,
+    redo: Vec<(L, T)>,
+    /// How many undo steps are kept, the oldest are dropped first.
+    pub limit: usize,
+}
+
+impl<T, L: Clone> UndoStack<T, L> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            limit,
+        }
+    }
+
+    /// Remembers `snapshot`, taken just before `label` happens. Anything that
+    /// could have been redone is forgotten.
+    pub fn push(&mut self, label: impl Into<L>, snapshot: T) {
+        self.redo.clear();
+        self.undo.push((label.into(), snapshot));
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Swaps `current` for the latest snapshot. Returns the label of what was
+    /// undone, or `None` if there is nothing left.
+    pub fn undo(&mut self, current: &mut T) -> Option<L> {
+        let (label, snapshot) = self.undo.pop()?;
+        let current = std::mem::replace(current, snapshot);
+        self.redo.push((label.clone(), current));
+        Some(label)
+    }
+
+    /// Reverses the last [`undo`](Self::undo).
+    pub fn redo(&mut self, current: &mut T) -> Option<L> {
+        let (label, snapshot) = self.redo.pop()?;
+        let current = std::mem::replace(current, snapshot);
+        self.undo.push((label.clone(), current));
+        Some(label)
+    }
+}